@@ -0,0 +1,402 @@
+// Cross-domain analyses over labs and symptom logs: lab-symptom correlation,
+// lagged symptom cross-correlation and per-test summaries.
+
+use crate::biological_variation;
+use crate::date_range::DateRange;
+use crate::stats;
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params, Connection};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What lab values are compared against: one symptom's severity or the daily
+/// wellness score.
+#[derive(Debug, Clone, Copy)]
+pub enum SymptomTarget {
+    Symptom(i64),
+    Wellness,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AlignedPair {
+    pub test_date: String,
+    pub lab_value: f64,
+    pub symptom_mean: f64,
+    pub days_logged: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct CorrelationResult {
+    pub test_name: String,
+    pub target: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub window_days: i64,
+    pub n: i64,
+    pub pearson: Option<f64>,
+    pub spearman: Option<f64>,
+    pub pairs: Vec<AlignedPair>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LagCorrelation {
+    pub lag: i64,
+    pub n: i64,
+    pub pearson: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CrossCorrelationResult {
+    pub series_a: String,
+    pub series_b: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub days_logged_a: i64,
+    pub days_logged_b: i64,
+    pub lags: Vec<LagCorrelation>,
+    pub best_lag: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsecutiveChange {
+    pub from_date: String,
+    pub to_date: String,
+    pub from_value: f64,
+    pub to_value: f64,
+    pub percent_change: Option<f64>,
+    pub exceeds_rcv: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TestSummary {
+    pub test_name: String,
+    pub unit: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub count: i64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    pub latest_value: Option<f64>,
+    pub latest_date: Option<String>,
+    pub baseline_value: Option<f64>,
+    pub baseline_date: Option<String>,
+    pub percent_change_from_baseline: Option<f64>,
+    pub slope_per_month: Option<f64>,
+    pub cv_biological: Option<f64>,
+    pub cv_analytical: Option<f64>,
+    pub rcv_percent: Option<f64>,
+    pub changes: Vec<ConsecutiveChange>,
+}
+
+pub fn symptom_id_by_name(conn: &Connection, name: &str) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT id FROM symptoms WHERE name = ?1 COLLATE NOCASE",
+        params![name],
+        |r| r.get(0),
+    )
+    .map_err(|_| format!("Unknown symptom: {}", name))
+}
+
+fn target_label(conn: &Connection, target: SymptomTarget) -> Result<String, String> {
+    match target {
+        SymptomTarget::Wellness => Ok("Wellness score".to_string()),
+        SymptomTarget::Symptom(id) => conn
+            .query_row("SELECT name FROM symptoms WHERE id = ?1", params![id], |r| r.get(0))
+            .map_err(|_| format!("Unknown symptom id: {}", id)),
+    }
+}
+
+/// Mean of the target over `start..=end` and the number of days it was drawn
+/// from. Only days that were logged at all count; for a symptom, a logged day
+/// without an entry for it counts as severity 0.
+pub fn target_mean(
+    conn: &Connection,
+    target: SymptomTarget,
+    start: &str,
+    end: &str,
+) -> Result<(Option<f64>, i64), String> {
+    let row = match target {
+        SymptomTarget::Wellness => conn.query_row(
+            "SELECT AVG(wellness_score), COUNT(*) FROM daily_summaries
+             WHERE log_date >= ?1 AND log_date <= ?2",
+            params![start, end],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        ),
+        SymptomTarget::Symptom(id) => conn.query_row(
+            "WITH days AS (
+                SELECT log_date FROM daily_summaries WHERE log_date >= ?1 AND log_date <= ?2
+                UNION
+                SELECT log_date FROM symptom_logs WHERE log_date >= ?1 AND log_date <= ?2
+             )
+             SELECT AVG(COALESCE((SELECT severity FROM symptom_logs sl
+                                  WHERE sl.log_date = days.log_date AND sl.symptom_id = ?3), 0)),
+                    COUNT(*)
+             FROM days",
+            params![start, end, id],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        ),
+    };
+    row.map_err(|e| e.to_string())
+}
+
+/// Widest averaging window either side of a lab draw.
+pub const MAX_WINDOW_DAYS: i64 = 30;
+
+pub fn validate_window_days(window_days: i64) -> Result<(), String> {
+    if (0..=MAX_WINDOW_DAYS).contains(&window_days) {
+        Ok(())
+    } else {
+        Err(format!("window_days must be between 0 and {}, got {}", MAX_WINDOW_DAYS, window_days))
+    }
+}
+
+/// Pairs each numeric result of `test_name` drawn within `range` with the
+/// target averaged over `window_days` either side of the draw, then
+/// correlates the pairs.
+pub fn lab_symptom_correlation(
+    conn: &Connection,
+    test_name: &str,
+    target: SymptomTarget,
+    window_days: i64,
+    range: &DateRange,
+) -> Result<CorrelationResult, String> {
+    validate_window_days(window_days)?;
+    let label = target_label(conn, target)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.test_date, r.value,
+                    date(s.test_date, '-' || ?3 || ' days'), date(s.test_date, '+' || ?3 || ' days')
+             FROM lab_results r
+             JOIN lab_sessions s ON r.session_id = s.id
+             WHERE r.test_name = ?1 AND r.value IS NOT NULL
+               AND s.test_date >= ?2 AND s.test_date <= ?4
+             ORDER BY s.test_date ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let draws: Vec<(String, f64, String, String)> = stmt
+        .query_map(params![test_name, range.start_date, window_days, range.end_date], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut pairs = Vec::new();
+    for (test_date, lab_value, start, end) in draws {
+        if let (Some(symptom_mean), days_logged) = target_mean(conn, target, &start, &end)? {
+            pairs.push(AlignedPair {
+                test_date,
+                lab_value,
+                symptom_mean,
+                days_logged,
+            });
+        }
+    }
+
+    let xs: Vec<f64> = pairs.iter().map(|p| p.lab_value).collect();
+    let ys: Vec<f64> = pairs.iter().map(|p| p.symptom_mean).collect();
+    Ok(CorrelationResult {
+        test_name: test_name.to_string(),
+        target: label,
+        start_date: range.start_date.clone(),
+        end_date: range.end_date.clone(),
+        window_days,
+        n: pairs.len() as i64,
+        pearson: stats::pearson(&xs, &ys),
+        spearman: stats::spearman(&xs, &ys),
+        pairs,
+    })
+}
+
+/// One value per logged day in `start..=end`. Days that were never logged are
+/// absent rather than zero; for a symptom, a logged day without an entry for
+/// it is 0.
+pub fn daily_series(
+    conn: &Connection,
+    target: SymptomTarget,
+    start: &str,
+    end: &str,
+) -> Result<BTreeMap<NaiveDate, f64>, String> {
+    let collect = |sql: &str, params: &[&dyn rusqlite::ToSql]| -> Result<Vec<(String, f64)>, String> {
+        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params, |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    };
+    let rows = match target {
+        SymptomTarget::Wellness => collect(
+            "SELECT log_date, CAST(wellness_score AS REAL) FROM daily_summaries
+             WHERE log_date >= ?1 AND log_date <= ?2",
+            params![start, end],
+        )?,
+        SymptomTarget::Symptom(id) => collect(
+            "WITH days AS (
+                SELECT log_date FROM daily_summaries WHERE log_date >= ?1 AND log_date <= ?2
+                UNION
+                SELECT log_date FROM symptom_logs WHERE log_date >= ?1 AND log_date <= ?2
+             )
+             SELECT log_date, CAST(COALESCE((SELECT severity FROM symptom_logs sl
+                                             WHERE sl.log_date = days.log_date AND sl.symptom_id = ?3), 0) AS REAL)
+             FROM days",
+            params![start, end, id],
+        )?,
+    };
+    let mut series = BTreeMap::new();
+    for (date, value) in rows {
+        let day = NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|e| e.to_string())?;
+        series.insert(day, value);
+    }
+    Ok(series)
+}
+
+/// Largest lag, in days, tried by `cross_correlation`.
+pub const MAX_LAG_DAYS: i64 = 60;
+
+/// Pearson correlation of `a` on day t against `b` on day t + lag, for every
+/// lag in `-max_lag..=max_lag` within `range`. A positive best lag
+/// means `b` tends to follow `a`. Only days where both series were logged
+/// contribute to a lag.
+pub fn cross_correlation(
+    conn: &Connection,
+    a: SymptomTarget,
+    b: SymptomTarget,
+    max_lag: i64,
+    range: &DateRange,
+) -> Result<CrossCorrelationResult, String> {
+    if !(0..=MAX_LAG_DAYS).contains(&max_lag) {
+        return Err(format!("max_lag must be between 0 and {}, got {}", MAX_LAG_DAYS, max_lag));
+    }
+    let series_a = daily_series(conn, a, &range.start_date, &range.end_date)?;
+    let series_b = daily_series(conn, b, &range.start_date, &range.end_date)?;
+
+    let lags: Vec<LagCorrelation> = (-max_lag..=max_lag)
+        .map(|lag| {
+            let (xs, ys): (Vec<f64>, Vec<f64>) = series_a
+                .iter()
+                .filter_map(|(day, x)| series_b.get(&(*day + Duration::days(lag))).map(|y| (*x, *y)))
+                .unzip();
+            LagCorrelation {
+                lag,
+                n: xs.len() as i64,
+                pearson: stats::pearson(&xs, &ys),
+            }
+        })
+        .collect();
+    let best_lag = lags
+        .iter()
+        .filter_map(|l| l.pearson.map(|r| (l.lag, r.abs())))
+        .max_by(|x, y| x.1.partial_cmp(&y.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(lag, _)| lag);
+
+    Ok(CrossCorrelationResult {
+        series_a: target_label(conn, a)?,
+        series_b: target_label(conn, b)?,
+        start_date: range.start_date.clone(),
+        end_date: range.end_date.clone(),
+        days_logged_a: series_a.len() as i64,
+        days_logged_b: series_b.len() as i64,
+        lags,
+        best_lag,
+    })
+}
+
+const DAYS_PER_MONTH: f64 = 365.25 / 12.0;
+
+fn percent_change(from: f64, to: f64) -> Option<f64> {
+    (from != 0.0).then(|| (to - from) / from.abs() * 100.0)
+}
+
+/// Descriptive statistics for the numeric results of `test_name` within
+/// `range`. The baseline is the first result in the window. Consecutive
+/// changes are compared against the reference change value when the test
+/// has bundled biological variation data.
+pub fn test_summary(conn: &Connection, test_name: &str, range: &DateRange) -> Result<TestSummary, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.test_date, r.value, r.unit
+             FROM lab_results r
+             JOIN lab_sessions s ON r.session_id = s.id
+             WHERE r.test_name = ?1 AND r.value IS NOT NULL
+               AND s.test_date >= ?2 AND s.test_date <= ?3
+             ORDER BY s.test_date ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<(String, f64, String)> = stmt
+        .query_map(params![test_name, range.start_date, range.end_date], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let values: Vec<f64> = rows.iter().map(|(_, v, _)| *v).collect();
+    let mut day_offsets = Vec::with_capacity(rows.len());
+    if let Some((first, _, _)) = rows.first() {
+        let first = NaiveDate::parse_from_str(first, "%Y-%m-%d").map_err(|e| e.to_string())?;
+        for (date, _, _) in &rows {
+            let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| e.to_string())?;
+            day_offsets.push((day - first).num_days() as f64);
+        }
+    }
+
+    let bv = biological_variation::lookup(test_name);
+    let rcv = bv.map(|(cvi, cva)| biological_variation::reference_change_value(cvi, cva));
+    let changes = rows
+        .windows(2)
+        .map(|pair| {
+            let pct = percent_change(pair[0].1, pair[1].1);
+            ConsecutiveChange {
+                from_date: pair[0].0.clone(),
+                to_date: pair[1].0.clone(),
+                from_value: pair[0].1,
+                to_value: pair[1].1,
+                percent_change: pct,
+                exceeds_rcv: rcv.zip(pct).map(|(rcv, pct)| pct.abs() > rcv),
+            }
+        })
+        .collect();
+
+    let first = rows.first();
+    let last = rows.last();
+    Ok(TestSummary {
+        test_name: test_name.to_string(),
+        unit: last.map(|(_, _, u)| u.clone()).unwrap_or_default(),
+        start_date: range.start_date.clone(),
+        end_date: range.end_date.clone(),
+        count: rows.len() as i64,
+        min: values.iter().cloned().reduce(f64::min),
+        max: values.iter().cloned().reduce(f64::max),
+        mean: stats::mean(&values),
+        latest_value: last.map(|(_, v, _)| *v),
+        latest_date: last.map(|(d, _, _)| d.clone()),
+        baseline_value: first.map(|(_, v, _)| *v),
+        baseline_date: first.map(|(d, _, _)| d.clone()),
+        percent_change_from_baseline: first.zip(last).and_then(|(f, l)| percent_change(f.1, l.1)),
+        slope_per_month: stats::linear_slope(&day_offsets, &values).map(|s| s * DAYS_PER_MONTH),
+        cv_biological: bv.map(|(cvi, _)| cvi),
+        cv_analytical: bv.map(|(_, cva)| cva),
+        rcv_percent: rcv,
+        changes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use std::path::Path;
+
+    #[test]
+    fn cross_correlation_bounds_the_lag() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        let range = DateRange { start_date: "2024-01-01".to_string(), end_date: "2024-03-31".to_string() };
+        let correlate = |max_lag| cross_correlation(&conn, SymptomTarget::Wellness, SymptomTarget::Wellness, max_lag, &range);
+
+        assert_eq!(correlate(MAX_LAG_DAYS).unwrap().lags.len() as i64, 2 * MAX_LAG_DAYS + 1);
+        assert!(correlate(MAX_LAG_DAYS + 1).is_err());
+        assert!(correlate(-1).is_err());
+        assert!(correlate(i64::MAX).is_err());
+    }
+}
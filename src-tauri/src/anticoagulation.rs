@@ -0,0 +1,195 @@
+// INR monitoring for patients on warfarin: the therapeutic range, time in
+// range by Rosendaal interpolation, and the weekly dose log.
+
+use crate::date_range::parse_date;
+use crate::stats;
+use rusqlite::{params, Connection};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub const INR_TEST_NAME: &str = "INR";
+pub const DEFAULT_TARGET_LOW: f64 = 2.0;
+pub const DEFAULT_TARGET_HIGH: f64 = 3.0;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, JsonSchema)]
+pub struct TherapeuticRange {
+    pub low: f64,
+    pub high: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct WarfarinDose {
+    pub id: Option<i64>,
+    pub week_start: String,
+    pub weekly_dose_mg: f64,
+    pub notes: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct InrPoint {
+    pub test_date: String,
+    pub value: f64,
+    pub in_range: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct TimeInRange {
+    pub days_assessed: i64,
+    pub days_in_range: i64,
+    pub days_below: i64,
+    pub days_above: i64,
+    pub ttr_percent: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct OutOfRangeStreak {
+    pub direction: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub result_count: i64,
+    pub extreme_value: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct InrReport {
+    pub start_date: String,
+    pub end_date: String,
+    pub therapeutic_range: TherapeuticRange,
+    pub points: Vec<InrPoint>,
+    pub time_in_range: TimeInRange,
+    pub streaks: Vec<OutOfRangeStreak>,
+    pub doses: Vec<WarfarinDose>,
+}
+
+fn setting_f64(conn: &Connection, key: &str) -> Option<f64> {
+    conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |r| {
+        r.get::<_, String>(0)
+    })
+    .ok()
+    .and_then(|v| v.parse().ok())
+}
+
+/// The clinician-set INR target, independent of the lab's reference range.
+pub fn therapeutic_range(conn: &Connection) -> TherapeuticRange {
+    TherapeuticRange {
+        low: setting_f64(conn, "inr_target_low").unwrap_or(DEFAULT_TARGET_LOW),
+        high: setting_f64(conn, "inr_target_high").unwrap_or(DEFAULT_TARGET_HIGH),
+    }
+}
+
+pub fn query_doses(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<WarfarinDose>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, week_start, weekly_dose_mg, notes FROM warfarin_doses
+             WHERE week_start >= date(?1, '-6 days') AND week_start <= ?2
+             ORDER BY week_start ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![start_date, end_date], |row| {
+            Ok(WarfarinDose {
+                id: Some(row.get(0)?),
+                week_start: row.get(1)?,
+                weekly_dose_mg: row.get(2)?,
+                notes: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// INR results in the period, plus the nearest result on either side so
+/// time in range can be interpolated up to the period boundaries.
+fn query_inr_with_bounds(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<(String, f64)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "WITH inr AS (
+                SELECT s.test_date, r.value FROM lab_results r
+                JOIN lab_sessions s ON r.session_id = s.id
+                WHERE r.test_name = ?1 AND r.value IS NOT NULL
+             )
+             SELECT test_date, value FROM inr WHERE test_date >= ?2 AND test_date <= ?3
+             UNION ALL
+             SELECT * FROM (SELECT test_date, value FROM inr WHERE test_date < ?2 ORDER BY test_date DESC LIMIT 1)
+             UNION ALL
+             SELECT * FROM (SELECT test_date, value FROM inr WHERE test_date > ?3 ORDER BY test_date ASC LIMIT 1)
+             ORDER BY test_date ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![INR_TEST_NAME, start_date, end_date], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn build_inr_report(conn: &Connection, start_date: &str, end_date: &str) -> Result<InrReport, String> {
+    let start = parse_date(start_date)?;
+    let end = parse_date(end_date)?;
+    if start > end {
+        return Err("start_date must not be after end_date".to_string());
+    }
+    let range = therapeutic_range(conn);
+
+    let all = query_inr_with_bounds(conn, start_date, end_date)?;
+    let mut dated = Vec::with_capacity(all.len());
+    for (date, value) in &all {
+        dated.push((parse_date(date)?, *value));
+    }
+    let rt = stats::rosendaal(&dated, range.low, range.high, start, end);
+
+    let in_period: Vec<&(String, f64)> = all
+        .iter()
+        .filter(|(d, _)| d.as_str() >= start_date && d.as_str() <= end_date)
+        .collect();
+    let values: Vec<f64> = in_period.iter().map(|(_, v)| *v).collect();
+    let streaks = stats::out_of_range_runs(&values, range.low, range.high)
+        .into_iter()
+        .map(|run| {
+            let slice = &values[run.first..=run.last];
+            let extreme = if run.below {
+                slice.iter().cloned().fold(f64::INFINITY, f64::min)
+            } else {
+                slice.iter().cloned().fold(f64::NEG_INFINITY, f64::max)
+            };
+            OutOfRangeStreak {
+                direction: if run.below { "low" } else { "high" }.to_string(),
+                start_date: in_period[run.first].0.clone(),
+                end_date: in_period[run.last].0.clone(),
+                result_count: (run.last - run.first + 1) as i64,
+                extreme_value: extreme,
+            }
+        })
+        .collect();
+
+    let points = in_period
+        .iter()
+        .map(|(d, v)| InrPoint {
+            test_date: d.clone(),
+            value: *v,
+            in_range: *v >= range.low && *v <= range.high,
+        })
+        .collect();
+
+    Ok(InrReport {
+        start_date: start_date.to_string(),
+        end_date: end_date.to_string(),
+        therapeutic_range: range,
+        points,
+        time_in_range: TimeInRange {
+            days_assessed: rt.days_assessed,
+            days_in_range: rt.days_in_range,
+            days_below: rt.days_below,
+            days_above: rt.days_above,
+            ttr_percent: (rt.days_assessed > 0)
+                .then(|| rt.days_in_range as f64 * 100.0 / rt.days_assessed as f64),
+        },
+        streaks,
+        doses: query_doses(conn, start_date, end_date)?,
+    })
+}
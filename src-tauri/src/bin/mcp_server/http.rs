@@ -0,0 +1,135 @@
+// Streamable HTTP transport for MCP clients that run as long-lived local
+// services. Every request must carry `Authorization: Bearer <token>` with the
+// token shown in the app's Settings; it is read from the database per request
+// so regenerating it takes effect immediately.
+
+use std::net::SocketAddr;
+
+use axum::{
+    Router,
+    extract::Request,
+    http::{StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+};
+use rmcp::transport::streamable_http_server::{
+    StreamableHttpService, session::local::LocalSessionManager,
+};
+
+use super::{TrackerMcp, open_db};
+
+pub const MCP_PATH: &str = "/mcp";
+
+fn http_token() -> Option<String> {
+    let conn = open_db().ok()?;
+    conn.query_row("SELECT value FROM settings WHERE key = 'mcp_http_token'", [], |r| r.get::<_, String>(0))
+        .ok()
+        .filter(|token| !token.is_empty())
+}
+
+/// Compares without short-circuiting so response timing doesn't reveal how
+/// much of the token matched.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+async fn require_bearer(request: Request, next: Next) -> Response {
+    let given = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match (given, http_token()) {
+        (Some(given), Some(expected)) if tokens_match(given, &expected) => next.run(request).await,
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "Missing or invalid bearer token. Copy the token from the Symptom Tracker app → Settings.",
+        )
+            .into_response(),
+    }
+}
+
+pub async fn serve(addr: SocketAddr) -> anyhow::Result<()> {
+    if http_token().is_none() {
+        anyhow::bail!("No HTTP token is configured. Open the Symptom Tracker app once to generate one.");
+    }
+
+    let service = StreamableHttpService::new(
+        || Ok(TrackerMcp::new()),
+        LocalSessionManager::default().into(),
+        Default::default(),
+    );
+    let router = Router::new()
+        .nest_service(MCP_PATH, service)
+        .layer(middleware::from_fn(require_bearer));
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("Serving MCP over HTTP at http://{}{}", listener.local_addr()?, MCP_PATH);
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::fixture;
+    use axum::{body::Body, routing::get};
+    use tower::ServiceExt;
+
+    fn token() -> String {
+        http_token().expect("the app generates a token")
+    }
+
+    async fn status(authorization: Option<&str>) -> StatusCode {
+        let router = Router::new()
+            .route(MCP_PATH, get(|| async { "ok" }))
+            .layer(middleware::from_fn(require_bearer));
+        let mut request = Request::builder().uri(MCP_PATH);
+        if let Some(authorization) = authorization {
+            request = request.header(header::AUTHORIZATION, authorization);
+        }
+        router.oneshot(request.body(Body::empty()).unwrap()).await.unwrap().status()
+    }
+
+    #[test]
+    fn tokens_must_match_exactly() {
+        assert!(tokens_match("abc123", "abc123"));
+        assert!(!tokens_match("abc124", "abc123"));
+        assert!(!tokens_match("abc12", "abc123"));
+        assert!(!tokens_match("", "abc123"));
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_current_bearer_token() {
+        let _mcp = fixture();
+        let token = token();
+        assert_eq!(status(Some(&format!("Bearer {}", token))).await, StatusCode::OK);
+        assert_eq!(status(None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(Some(&format!("Basic {}", token))).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(Some("Bearer wrong")).await, StatusCode::UNAUTHORIZED);
+
+        let app = rusqlite::Connection::open(crate::db_path()).unwrap();
+        app.execute("UPDATE settings SET value = 'rotated' WHERE key = 'mcp_http_token'", []).unwrap();
+        assert_eq!(status(Some(&format!("Bearer {}", token))).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(Some("Bearer rotated")).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn nothing_is_served_without_a_token() {
+        let _mcp = fixture();
+        let token = token();
+        let app = rusqlite::Connection::open(crate::db_path()).unwrap();
+        app.execute("UPDATE settings SET value = '' WHERE key = 'mcp_http_token'", []).unwrap();
+        assert_eq!(status(Some(&format!("Bearer {}", token))).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(Some("Bearer ")).await, StatusCode::UNAUTHORIZED);
+
+        let error = serve("127.0.0.1:0".parse().unwrap()).await.unwrap_err();
+        assert!(error.to_string().contains("No HTTP token"), "{}", error);
+    }
+}
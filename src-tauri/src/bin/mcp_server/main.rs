@@ -0,0 +1,2292 @@
+mod http;
+#[cfg(test)]
+mod tests;
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use rmcp::{
+    ErrorData as McpError, RoleServer, ServerHandler, ServiceExt,
+    handler::server::{
+        common::schema_for_output,
+        tool::{ToolCallContext, ToolRouter},
+        wrapper::Parameters,
+    },
+    model::*,
+    schemars,
+    service::RequestContext,
+    tool, tool_router,
+    transport::stdio,
+};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri_app_lib::analysis::{self, CorrelationResult, SymptomTarget};
+use tauri_app_lib::anticoagulation::{build_inr_report, InrReport};
+use tauri_app_lib::date_range::{self, DateRange};
+use tauri_app_lib::db;
+use tauri_app_lib::flares::{query_flares_with_labs, FlareWithLabs};
+use tauri_app_lib::labs::{self, LabResult, LabSession};
+use tauri_app_lib::mcp_access_log::{self, NewAccess, KIND_PROMPT, KIND_RESOURCE, KIND_TOOL};
+use tauri_app_lib::mcp_policy::{self, McpPolicy, CATEGORY_FLARES, CATEGORY_LABS, CATEGORY_SYMPTOMS};
+use tauri_app_lib::mcp_writes::{self, WriteTarget, SOURCE_MCP};
+use tauri_app_lib::rollups::{self, Rollup, RollupPeriod};
+use tauri_app_lib::search::{self, NoteHit};
+use tauri_app_lib::symptoms::{self, SymptomEntry};
+use tauri_app_lib::vitals::{self, VitalReading};
+
+fn db_path() -> PathBuf {
+    #[cfg(test)]
+    if let Some(path) = tests::fixture_db_path() {
+        return path;
+    }
+    let base = dirs::data_local_dir().expect("Could not determine local data directory");
+    base.join("symptom-test-tracker").join("tracker.db")
+}
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Idle read connections kept for reuse.
+const READ_POOL_SIZE: usize = 4;
+
+/// Idle read connections, each with the policy its views were built for.
+static READ_POOL: Mutex<Vec<PooledConn>> = Mutex::new(Vec::new());
+
+struct PooledConn {
+    path: PathBuf,
+    conn: Connection,
+    policy: McpPolicy,
+}
+
+/// Opens the tracker database, failing clearly when the app hasn't created it
+/// yet or has migrated it past what this build understands.
+fn connect(path: &Path, flags: rusqlite::OpenFlags) -> Result<Connection, McpError> {
+    if !path.exists() {
+        return Err(McpError::internal_error(
+            format!("No Symptom Tracker database at {}. Open the app once to create it.", path.display()),
+            None,
+        ));
+    }
+    let conn = Connection::open_with_flags(path, flags)
+        .map_err(|e| McpError::internal_error(format!("Failed to open database: {}", e), None))?;
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    check_schema_version(&conn)?;
+    Ok(conn)
+}
+
+fn check_schema_version(conn: &Connection) -> Result<(), McpError> {
+    let version = db::schema_version(conn)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    if version > db::SCHEMA_VERSION {
+        return Err(McpError::internal_error(
+            format!(
+                "The database uses schema version {}, but this MCP server only understands up to {}. Update the MCP server to match the Symptom Tracker app.",
+                version,
+                db::SCHEMA_VERSION
+            ),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+/// Read-only connection that sees only what the app's MCP access policy
+/// allows (see `mcp_policy`). Connections are reused across calls, and each
+/// checkout runs in one read transaction so every query in a tool sees the
+/// same snapshot while the app writes.
+fn open_db() -> Result<ReadConn, McpError> {
+    let path = db_path();
+    let pooled = {
+        let mut pool = READ_POOL.lock().unwrap_or_else(|e| e.into_inner());
+        pool.iter().rposition(|p| p.path == path).map(|i| pool.swap_remove(i))
+    };
+    let reusable = match &pooled {
+        // The app may have been upgraded, or the policy changed since the
+        // views were built, while the connection sat in the pool
+        Some(pooled) if path.exists() => {
+            check_schema_version(&pooled.conn)?;
+            load_policy(&pooled.conn)? == pooled.policy
+        }
+        _ => false,
+    };
+    let (conn, policy) = match pooled {
+        Some(pooled) if reusable => (pooled.conn, pooled.policy),
+        _ => {
+            let conn = connect(&path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+            let policy = load_policy(&conn)?;
+            mcp_policy::apply_read_views(&conn, &policy)
+                .map_err(|e| McpError::internal_error(e, None))?;
+            (conn, policy)
+        }
+    };
+    conn.execute_batch("BEGIN")
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    Ok(ReadConn { pooled: Some(PooledConn { path, conn, policy }) })
+}
+
+/// A pooled read connection inside its read transaction. Ends the
+/// transaction and returns the connection to the pool when dropped.
+struct ReadConn {
+    pooled: Option<PooledConn>,
+}
+
+impl std::ops::Deref for ReadConn {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.pooled.as_ref().expect("connection present until drop").conn
+    }
+}
+
+impl Drop for ReadConn {
+    fn drop(&mut self) {
+        let Some(pooled) = self.pooled.take() else { return };
+        if pooled.conn.execute_batch("COMMIT").is_err() {
+            return;
+        }
+        let mut pool = READ_POOL.lock().unwrap_or_else(|e| e.into_inner());
+        if pool.len() < READ_POOL_SIZE {
+            pool.push(pooled);
+        }
+    }
+}
+
+/// Read-write connection for the write tools. Waits for the app's own writes
+/// instead of failing immediately on a locked database.
+fn open_db_for_write() -> Result<Connection, McpError> {
+    let conn = connect(&db_path(), rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+    conn.execute_batch("PRAGMA foreign_keys=ON;")
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    Ok(conn)
+}
+
+fn check_enabled(conn: &Connection) -> Result<(), McpError> {
+    let enabled: String = conn
+        .query_row("SELECT value FROM settings WHERE key = 'mcp_enabled'", [], |r| r.get(0))
+        .unwrap_or_default();
+    if enabled != "true" {
+        return Err(McpError::internal_error(
+            "MCP access is currently disabled in the Symptom Tracker app. Open the app → Settings to re-enable.".to_string(),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+fn check_write_enabled(conn: &Connection) -> Result<(), McpError> {
+    check_enabled(conn)?;
+    let enabled: String = conn
+        .query_row("SELECT value FROM settings WHERE key = 'mcp_write_enabled'", [], |r| r.get(0))
+        .unwrap_or_default();
+    if enabled != "true" {
+        return Err(McpError::internal_error(
+            "MCP write access is disabled in the Symptom Tracker app. Open the app → Settings and allow MCP clients to log data to use this tool.".to_string(),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+fn load_policy(conn: &Connection) -> Result<McpPolicy, McpError> {
+    McpPolicy::load(conn).map_err(|e| McpError::internal_error(e, None))
+}
+
+fn policy_blocked(what: &str) -> McpError {
+    McpError::invalid_params(
+        format!("{} is not available under the MCP access settings in the Symptom Tracker app", what),
+        None,
+    )
+}
+
+/// The tools the access policy lets clients see and call.
+fn allowed_tools(router: &ToolRouter<TrackerMcp>) -> Result<Vec<Tool>, McpError> {
+    let policy = open_db().and_then(|conn| load_policy(&conn))?;
+    Ok(router.list_all().into_iter().filter(|tool| policy.allows_tool(&tool.name)).collect())
+}
+
+fn check_tool_allowed(name: &str) -> Result<(), McpError> {
+    if !open_db().and_then(|conn| load_policy(&conn))?.allows_tool(name) {
+        return Err(policy_blocked(&format!("Tool {}", name)));
+    }
+    Ok(())
+}
+
+/// Rejects a write dated before the policy's look-back limit, which the
+/// client could not read back.
+fn check_write_date(conn: &Connection, policy: &McpPolicy, date: &str) -> Result<(), McpError> {
+    date_range::parse_date(date).map_err(|e| McpError::invalid_params(e, None))?;
+    if let Some(cutoff) = policy.cutoff_date(conn).map_err(|e| McpError::internal_error(e, None))? {
+        if date < cutoff.as_str() {
+            return Err(policy_blocked(&format!("Writing to {} (before {})", date, cutoff)));
+        }
+    }
+    Ok(())
+}
+
+/// Resolves the window and clamps its start to the policy's look-back limit,
+/// so the echoed range is the one the data actually covers.
+fn resolve_range(
+    conn: &Connection,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<DateRange, McpError> {
+    let mut range = date_range::resolve(conn, days, start_date.as_deref(), end_date.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+    let policy = load_policy(conn)?;
+    if let Some(cutoff) = policy.cutoff_date(conn).map_err(|e| McpError::internal_error(e, None))? {
+        if range.end_date < cutoff {
+            return Err(McpError::invalid_params(
+                format!("MCP access is limited to dates from {} onwards", cutoff),
+                None,
+            ));
+        }
+        if range.start_date < cutoff {
+            range.start_date = cutoff;
+        }
+    }
+    Ok(range)
+}
+
+// Pagination
+const DEFAULT_PAGE_SIZE: i64 = 100;
+const MAX_PAGE_SIZE: i64 = 500;
+
+/// Where a paged listing resumes: the range being paged and the key of the
+/// last item returned. Clients get it hex-encoded as an opaque string.
+#[derive(Debug, Serialize, Deserialize)]
+struct PageCursor {
+    start_date: String,
+    end_date: String,
+    after_date: String,
+    after_id: Option<i64>,
+}
+
+fn encode_cursor(cursor: &PageCursor) -> String {
+    serde_json::to_vec(cursor)
+        .unwrap_or_default()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn decode_cursor(text: &str) -> Result<PageCursor, McpError> {
+    let invalid = || McpError::invalid_params("Invalid cursor; start again without one".to_string(), None);
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return Err(invalid());
+    }
+    let bytes = (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+    serde_json::from_slice(&bytes).map_err(|_| invalid())
+}
+
+/// A paged request resolved against the database: the range to page through,
+/// the page size and where to resume.
+struct PageRequest {
+    range: DateRange,
+    limit: i64,
+    after: Option<PageCursor>,
+    omit_fields: Vec<String>,
+}
+
+impl PageRequest {
+    fn resolve(conn: &Connection, param: PagedRangeParam) -> Result<Self, McpError> {
+        let limit = param.max_items.unwrap_or(DEFAULT_PAGE_SIZE);
+        if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+            return Err(McpError::invalid_params(
+                format!("max_items must be between 1 and {}", MAX_PAGE_SIZE),
+                None,
+            ));
+        }
+        let (range, after) = match param.cursor {
+            Some(cursor) => {
+                let cursor = decode_cursor(&cursor)?;
+                let range = resolve_range(conn, None, Some(cursor.start_date.clone()), Some(cursor.end_date.clone()))?;
+                (range, Some(cursor))
+            }
+            None => (resolve_range(conn, param.range.days, param.range.start_date, param.range.end_date)?, None),
+        };
+        Ok(Self { range, limit, after, omit_fields: param.omit_fields.unwrap_or_default() })
+    }
+
+    fn after_date(&self) -> Option<&str> {
+        self.after.as_ref().map(|c| c.after_date.as_str())
+    }
+
+    fn after_id(&self) -> Option<i64> {
+        self.after.as_ref().and_then(|c| c.after_id)
+    }
+
+    /// Rows to fetch: one more than the page so the next page can be detected.
+    fn fetch_limit(&self) -> i64 {
+        self.limit + 1
+    }
+
+    /// Drops the extra row fetched past the page and returns the cursor for
+    /// the next page, if there is one. `key` gives a row's date and id.
+    fn next_cursor<T>(&self, rows: &mut Vec<T>, key: impl Fn(&T) -> (String, Option<i64>)) -> Option<String> {
+        if rows.len() as i64 <= self.limit {
+            return None;
+        }
+        rows.truncate(self.limit as usize);
+        let (after_date, after_id) = key(rows.last()?);
+        Some(encode_cursor(&PageCursor {
+            start_date: self.range.start_date.clone(),
+            end_date: self.range.end_date.clone(),
+            after_date,
+            after_id,
+        }))
+    }
+
+    fn page<T: Serialize>(&self, total: i64, next_cursor: Option<String>, results: Vec<T>) -> PageOut<T> {
+        PageOut {
+            start_date: self.range.start_date.clone(),
+            end_date: self.range.end_date.clone(),
+            total,
+            returned: results.len(),
+            next_cursor,
+            results,
+        }
+    }
+}
+
+// Parameter structs
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RangeParam {
+    #[schemars(description = "Number of days to look back from end_date (or today). Shorthand for start_date")]
+    pub days: Option<i64>,
+    #[schemars(description = "Start date (YYYY-MM-DD), inclusive")]
+    pub start_date: Option<String>,
+    #[schemars(description = "End date (YYYY-MM-DD), inclusive. Defaults to today")]
+    pub end_date: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct PagedRangeParam {
+    #[serde(flatten)]
+    pub range: RangeParam,
+    #[schemars(description = "next_cursor from the previous page. The page continues the same date range, so days, start_date and end_date are ignored")]
+    pub cursor: Option<String>,
+    #[schemars(description = "Maximum number of items per page (default 100, at most 500)")]
+    pub max_items: Option<i64>,
+    #[schemars(description = "Field names to leave out of every item, at any depth, e.g. [\"notes\", \"daily_notes\"]")]
+    pub omit_fields: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct TrendsParam {
+    #[schemars(description = "Name of the test to get trends for")]
+    pub test_name: String,
+    #[serde(flatten)]
+    pub range: RangeParam,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CorrelationParam {
+    #[schemars(description = "Name of the lab test, e.g. \"C3\"")]
+    pub test_name: String,
+    #[schemars(description = "Symptom name, e.g. \"Joint Pain\". Omit to use the daily wellness score")]
+    pub symptom: Option<String>,
+    #[schemars(description = "Days either side of each lab draw to average symptom severity over, 0-30 (default 3)")]
+    pub window_days: Option<i64>,
+    #[serde(flatten)]
+    pub range: RangeParam,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RollupParam {
+    #[schemars(description = "Bucket size: \"week\" (ISO week) or \"month\"")]
+    pub period: String,
+    #[serde(flatten)]
+    pub range: RangeParam,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SearchParam {
+    #[schemars(description = "Words to search for in lab, symptom and daily notes; all must match, the last may be a prefix")]
+    pub query: String,
+    #[schemars(description = "Maximum number of hits (default 50)")]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SymptomInput {
+    #[schemars(description = "Symptom name as configured in the app (case-insensitive)")]
+    pub symptom: String,
+    #[schemars(description = "Severity from 0 (absent) to 10. The app's entry form records 1 for present")]
+    pub severity: i64,
+    #[schemars(description = "Notes for this symptom. Omit to keep any existing notes")]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct LogSymptomsParam {
+    #[schemars(description = "Date to log (YYYY-MM-DD). Defaults to today")]
+    pub date: Option<String>,
+    #[schemars(description = "Symptoms to record. Other symptoms already logged that day are kept")]
+    pub symptoms: Vec<SymptomInput>,
+    #[schemars(description = "Wellness score from 1 (worst) to 5 (best). Defaults to the day's existing score")]
+    pub wellness: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct LabResultInput {
+    #[schemars(description = "Test name, matching earlier results for the same test")]
+    pub test_name: String,
+    #[schemars(description = "Panel the test belongs to")]
+    pub panel: Option<String>,
+    #[schemars(description = "Numeric value")]
+    pub value: Option<f64>,
+    #[schemars(description = "Text value for non-numeric results")]
+    pub text_value: Option<String>,
+    pub unit: Option<String>,
+    pub ref_range_low: Option<f64>,
+    pub ref_range_high: Option<f64>,
+    #[schemars(description = "Flag (N, H, L, HH, LL). Computed from the reference range when omitted")]
+    pub flag: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct AddLabSessionParam {
+    #[schemars(description = "Date the labs were drawn (YYYY-MM-DD)")]
+    pub test_date: String,
+    pub lab_name: Option<String>,
+    pub notes: Option<String>,
+    #[schemars(description = "Results in the session. Missing panel, unit and reference range are filled from the test's most recent earlier result")]
+    pub results: Vec<LabResultInput>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct DailyNoteParam {
+    #[schemars(description = "Date of the note (YYYY-MM-DD). Defaults to today")]
+    pub date: Option<String>,
+    #[schemars(description = "Note text, appended to any existing notes for the day")]
+    pub note: String,
+    #[schemars(description = "Wellness score from 1 (worst) to 5 (best). Defaults to the day's existing score")]
+    pub wellness: Option<i64>,
+}
+
+// Response structs. Every tool's result is a JSON object so it can be
+// published as the tool's output schema.
+/// Wraps a tool's rows with the date range they were drawn from.
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct RangedOut<T: Serialize> {
+    start_date: String,
+    end_date: String,
+    results: T,
+}
+
+impl<T: Serialize> RangedOut<T> {
+    fn new(range: DateRange, results: T) -> Self {
+        Self {
+            start_date: range.start_date,
+            end_date: range.end_date,
+            results,
+        }
+    }
+}
+
+/// One page of a tool's rows, newest first. `total` counts every item in the
+/// range; `next_cursor` is set while more pages remain.
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct PageOut<T: Serialize> {
+    start_date: String,
+    end_date: String,
+    total: i64,
+    returned: usize,
+    next_cursor: Option<String>,
+    results: Vec<T>,
+}
+
+/// Wraps a tool's rows when they aren't tied to a date range.
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct ListOut<T: Serialize> {
+    results: Vec<T>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct LabSessionResult {
+    id: i64,
+    test_date: String,
+    lab_name: String,
+    notes: String,
+    results: Vec<LabResultRow>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct LabResultRow {
+    test_name: String,
+    panel: String,
+    value: Option<f64>,
+    text_value: String,
+    unit: String,
+    ref_range_low: Option<f64>,
+    ref_range_high: Option<f64>,
+    flag: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct AbnormalResult {
+    test_name: String,
+    panel: String,
+    value: Option<f64>,
+    text_value: String,
+    unit: String,
+    ref_range_low: Option<f64>,
+    ref_range_high: Option<f64>,
+    flag: String,
+    test_date: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct SymptomDay {
+    date: String,
+    wellness_score: i64,
+    daily_notes: String,
+    symptoms: Vec<SymptomLogRow>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct SymptomLogRow {
+    symptom_name: String,
+    category: String,
+    severity: i64,
+    notes: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct TrendPointOut {
+    test_date: String,
+    value: Option<f64>,
+    text_value: String,
+    flag: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct TestNameOut {
+    test_name: String,
+    panel: String,
+    unit: String,
+    count: i64,
+    last_date: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct SymptomOut {
+    name: String,
+    category: String,
+    active: bool,
+    description: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct CustomTestOut {
+    name: String,
+    panel: String,
+    unit: String,
+    ref_low: Option<f64>,
+    ref_high: Option<f64>,
+    text_only: bool,
+    description: String,
+    clinical: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct WriteOut {
+    write_id: i64,
+    summary: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+struct DailySummaryOut {
+    date: String,
+    wellness_score: i64,
+    notes: String,
+}
+
+// Structured tool results
+/// Output schema for a tool returning `T`, published in the tool list.
+fn output_schema<T: schemars::JsonSchema + 'static>() -> Arc<JsonObject> {
+    schema_for_output::<T>().expect("tool results are JSON objects")
+}
+
+/// Tool result carrying `data` as structured content matching the tool's
+/// output schema. The text content is a one-line summary followed by the
+/// compact JSON, for clients that only read text.
+fn structured_result(data: &impl Serialize, summary: String) -> Result<CallToolResult, McpError> {
+    let value = serde_json::to_value(data)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let mut result = CallToolResult::structured(value);
+    result.content.insert(0, Content::text(summary));
+    Ok(result)
+}
+
+/// Output schema for a paged tool returning `T` items. `omit_fields` can drop
+/// any item field, so none of them is required.
+fn page_output_schema<T: schemars::JsonSchema + Serialize + 'static>() -> Arc<JsonObject> {
+    let mut schema = output_schema::<PageOut<T>>();
+    if let Some(serde_json::Value::Object(defs)) = Arc::make_mut(&mut schema).get_mut("$defs") {
+        for def in defs.values_mut().filter_map(|def| def.as_object_mut()) {
+            def.remove("required");
+        }
+    }
+    schema
+}
+
+/// Page result with `omit_fields` removed from every item. Unknown field
+/// names are rejected so typos don't silently return everything.
+fn paged_result<T: schemars::JsonSchema + Serialize>(
+    page: &PageOut<T>,
+    omit_fields: &[String],
+    mut summary: String,
+) -> Result<CallToolResult, McpError> {
+    if page.next_cursor.is_some() {
+        summary.push_str("; more with next_cursor");
+    }
+    let mut value = serde_json::to_value(page)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    if !omit_fields.is_empty() {
+        let item_schema = serde_json::to_value(schemars::schema_for!(T))
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let mut known = Vec::new();
+        collect_property_names(&item_schema, &mut known);
+        if let Some(unknown) = omit_fields.iter().find(|f| !known.contains(f)) {
+            known.sort();
+            known.dedup();
+            return Err(McpError::invalid_params(
+                format!("Unknown field \"{}\". Fields: {}", unknown, known.join(", ")),
+                None,
+            ));
+        }
+        if let Some(items) = value.get_mut("results").and_then(|r| r.as_array_mut()) {
+            for item in items {
+                omit(item, omit_fields);
+            }
+        }
+    }
+    let mut result = CallToolResult::structured(value);
+    result.content.insert(0, Content::text(summary));
+    Ok(result)
+}
+
+fn collect_property_names(schema: &serde_json::Value, names: &mut Vec<String>) {
+    match schema {
+        serde_json::Value::Object(fields) => {
+            if let Some(serde_json::Value::Object(properties)) = fields.get("properties") {
+                names.extend(properties.keys().cloned());
+            }
+            for value in fields.values() {
+                collect_property_names(value, names);
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                collect_property_names(value, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn omit(value: &mut serde_json::Value, fields: &[String]) {
+    match value {
+        serde_json::Value::Object(object) => {
+            object.retain(|key, _| !fields.contains(key));
+            for child in object.values_mut() {
+                omit(child, fields);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                omit(item, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn page_text(returned: usize, total: i64, singular: &str, plural: &str) -> String {
+    let noun = if total == 1 { singular } else { plural };
+    if returned as i64 == total {
+        format!("{} {}", total, noun)
+    } else {
+        format!("{} of {} {}", returned, total, noun)
+    }
+}
+
+fn counted(n: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", n, if n == 1 { singular } else { plural })
+}
+
+fn range_text(range: &DateRange) -> String {
+    format!("{} to {}", range.start_date, range.end_date)
+}
+
+// Shared queries
+fn session_results(conn: &Connection, session_id: i64) -> Result<Vec<LabResultRow>, McpError> {
+    let mut stmt = conn.prepare(
+        "SELECT test_name, panel, value, text_value, unit, ref_range_low, ref_range_high, flag
+         FROM lab_results WHERE session_id = ?1 ORDER BY panel, test_name",
+    ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let results = stmt.query_map(params![session_id], |row| {
+        Ok(LabResultRow {
+            test_name: row.get(0)?,
+            panel: row.get(1)?,
+            value: row.get(2)?,
+            text_value: row.get(3)?,
+            unit: row.get(4)?,
+            ref_range_low: row.get(5)?,
+            ref_range_high: row.get(6)?,
+            flag: row.get(7)?,
+        })
+    }).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    Ok(results)
+}
+
+/// Latest result per test where the flag is not normal.
+fn abnormal_labs(conn: &Connection) -> Result<Vec<AbnormalResult>, McpError> {
+    let mut stmt = conn.prepare(
+        "SELECT r.test_name, r.panel, r.value, r.text_value, r.unit,
+                r.ref_range_low, r.ref_range_high, r.flag, s.test_date
+         FROM lab_results r
+         INNER JOIN (
+             SELECT test_name, MAX(s.test_date) as max_date
+             FROM lab_results lr
+             JOIN lab_sessions s ON lr.session_id = s.id
+             GROUP BY test_name
+         ) latest ON r.test_name = latest.test_name
+         INNER JOIN lab_sessions s ON r.session_id = s.id AND s.test_date = latest.max_date
+         WHERE r.flag != 'N' AND r.flag != ''
+         ORDER BY r.panel, r.test_name",
+    ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let results = stmt.query_map([], |row| {
+        Ok(AbnormalResult {
+            test_name: row.get(0)?,
+            panel: row.get(1)?,
+            value: row.get(2)?,
+            text_value: row.get(3)?,
+            unit: row.get(4)?,
+            ref_range_low: row.get(5)?,
+            ref_range_high: row.get(6)?,
+            flag: row.get(7)?,
+            test_date: row.get(8)?,
+        })
+    }).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    Ok(results)
+}
+
+/// Wellness, daily notes and symptom entries for one date. Days without a
+/// summary report `symptoms::DEFAULT_WELLNESS`.
+fn symptom_day(conn: &Connection, date: String) -> Result<SymptomDay, McpError> {
+    let (wellness_score, daily_notes) = conn.query_row(
+        "SELECT wellness_score, notes FROM daily_summaries WHERE log_date = ?1",
+        params![date],
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+    ).unwrap_or((symptoms::DEFAULT_WELLNESS, String::new()));
+
+    let mut stmt = conn.prepare(
+        "SELECT s.name, s.category, sl.severity, sl.notes
+         FROM symptom_logs sl
+         JOIN symptoms s ON sl.symptom_id = s.id
+         WHERE sl.log_date = ?1
+         ORDER BY s.sort_order",
+    ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let symptoms = stmt.query_map(params![date], |row| {
+        Ok(SymptomLogRow {
+            symptom_name: row.get(0)?,
+            category: row.get(1)?,
+            severity: row.get(2)?,
+            notes: row.get(3)?,
+        })
+    }).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    Ok(SymptomDay { date, wellness_score, daily_notes, symptoms })
+}
+
+// Resources
+const RESOURCE_MIME_TYPE: &str = "application/json";
+
+/// How many recent lab sessions and logged days `resources/list` enumerates.
+/// Older records stay reachable through the resource templates.
+const LISTED_RECENT_LIMIT: i64 = 30;
+
+#[derive(Debug, Serialize)]
+struct TestHistory {
+    test_name: String,
+    unit: String,
+    points: Vec<TrendPointOut>,
+}
+
+#[derive(Debug, Serialize)]
+struct CurrentSummary {
+    as_of: String,
+    latest_lab_session: Option<LabSessionResult>,
+    abnormal_labs: Vec<AbnormalResult>,
+    mean_wellness_30d: Option<f64>,
+    recent_days: Vec<SymptomDay>,
+}
+
+fn resource(uri: String, name: String, description: &str) -> Resource {
+    RawResource {
+        description: Some(description.to_string()),
+        mime_type: Some(RESOURCE_MIME_TYPE.to_string()),
+        ..RawResource::new(uri, name)
+    }
+    .no_annotation()
+}
+
+fn resource_template(uri_template: &str, name: &str, description: &str) -> ResourceTemplate {
+    RawResourceTemplate {
+        uri_template: uri_template.to_string(),
+        name: name.to_string(),
+        title: None,
+        description: Some(description.to_string()),
+        mime_type: Some(RESOURCE_MIME_TYPE.to_string()),
+        icons: None,
+    }
+    .no_annotation()
+}
+
+fn resource_not_found(uri: &str) -> McpError {
+    McpError::resource_not_found(format!("No resource at {}", uri), None)
+}
+
+/// Decodes `%XX` escapes in a URI segment, so test names containing `/` or
+/// spaces can be addressed. Each escape needs exactly two hex digits.
+fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = segment.get(i + 1..i + 3).filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Category a resource path needs; the summary needs labs or symptoms.
+fn resource_allowed(policy: &McpPolicy, segments: &[&str]) -> bool {
+    match segments.first() {
+        Some(&"labs") | Some(&"tests") => policy.allows_category(CATEGORY_LABS),
+        Some(&"symptoms") => policy.allows_category(CATEGORY_SYMPTOMS),
+        _ => policy.allows_category(CATEGORY_LABS) || policy.allows_category(CATEGORY_SYMPTOMS),
+    }
+}
+
+fn list_tracker_resources(conn: &Connection, policy: &McpPolicy) -> Result<Vec<Resource>, McpError> {
+    let mut resources = Vec::new();
+    if resource_allowed(policy, &["summary"]) {
+        resources.push(resource(
+            "tracker://summary/current".to_string(),
+            "Current health summary".to_string(),
+            "Latest lab session, currently abnormal labs, 30-day mean wellness and the last 7 days of symptom logs",
+        ));
+    }
+
+    // Blocked categories leave these tables empty through the policy views
+    let mut stmt = conn.prepare(
+        "SELECT id, test_date, lab_name FROM lab_sessions
+         ORDER BY test_date DESC, id DESC LIMIT ?1",
+    ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let sessions: Vec<(i64, String, String)> = stmt.query_map(params![LISTED_RECENT_LIMIT], |row| {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    }).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    for (id, test_date, lab_name) in sessions {
+        let name = if lab_name.is_empty() {
+            format!("Lab session {}", test_date)
+        } else {
+            format!("Lab session {} ({})", test_date, lab_name)
+        };
+        resources.push(resource(
+            format!("tracker://labs/session/{}", id),
+            name,
+            "All results from one lab draw with flags and reference ranges",
+        ));
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT log_date FROM daily_summaries
+         UNION
+         SELECT log_date FROM symptom_logs
+         ORDER BY log_date DESC LIMIT ?1",
+    ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let dates: Vec<String> = stmt.query_map(params![LISTED_RECENT_LIMIT], |row| row.get(0))
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    for date in dates {
+        resources.push(resource(
+            format!("tracker://symptoms/day/{}", date),
+            format!("Symptoms {}", date),
+            "Wellness score, daily notes and symptom severities for one day",
+        ));
+    }
+
+    Ok(resources)
+}
+
+fn read_lab_session(conn: &Connection, uri: &str, id: i64) -> Result<LabSessionResult, McpError> {
+    let (test_date, lab_name, notes) = conn.query_row(
+        "SELECT test_date, lab_name, notes FROM lab_sessions WHERE id = ?1",
+        params![id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).map_err(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => resource_not_found(uri),
+        e => McpError::internal_error(e.to_string(), None),
+    })?;
+    let results = session_results(conn, id)?;
+    Ok(LabSessionResult { id, test_date, lab_name, notes, results })
+}
+
+fn read_symptom_day(conn: &Connection, uri: &str, date: &str) -> Result<SymptomDay, McpError> {
+    date_range::parse_date(date).map_err(|e| McpError::invalid_params(e, None))?;
+    let logged: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM daily_summaries WHERE log_date = ?1)
+             OR EXISTS(SELECT 1 FROM symptom_logs WHERE log_date = ?1)",
+        params![date],
+        |row| row.get(0),
+    ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    if !logged {
+        return Err(resource_not_found(uri));
+    }
+    symptom_day(conn, date.to_string())
+}
+
+fn read_test_history(conn: &Connection, uri: &str, test_name: String) -> Result<TestHistory, McpError> {
+    let mut stmt = conn.prepare(
+        "SELECT s.test_date, r.value, r.text_value, r.flag, r.unit
+         FROM lab_results r
+         JOIN lab_sessions s ON r.session_id = s.id
+         WHERE r.test_name = ?1
+         ORDER BY s.test_date ASC",
+    ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let mut unit = String::new();
+    let mut points = Vec::new();
+    let rows = stmt.query_map(params![test_name], |row| {
+        Ok((
+            TrendPointOut {
+                test_date: row.get(0)?,
+                value: row.get(1)?,
+                text_value: row.get(2)?,
+                flag: row.get(3)?,
+            },
+            row.get::<_, String>(4)?,
+        ))
+    }).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    for row in rows {
+        let (point, row_unit) = row.map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        if !row_unit.is_empty() {
+            unit = row_unit;
+        }
+        points.push(point);
+    }
+    if points.is_empty() {
+        return Err(resource_not_found(uri));
+    }
+    Ok(TestHistory { test_name, unit, points })
+}
+
+fn read_current_summary(conn: &Connection) -> Result<CurrentSummary, McpError> {
+    let week = resolve_range(conn, Some(6), None, None)?;
+    let month = resolve_range(conn, Some(29), None, None)?;
+
+    let latest_id: Option<i64> = conn.query_row(
+        "SELECT id FROM lab_sessions ORDER BY test_date DESC, id DESC LIMIT 1",
+        [],
+        |row| row.get(0),
+    ).ok();
+    let latest_lab_session = match latest_id {
+        Some(id) => Some(read_lab_session(conn, "tracker://summary/current", id)?),
+        None => None,
+    };
+
+    let mean_wellness_30d: Option<f64> = conn.query_row(
+        "SELECT AVG(wellness_score) FROM daily_summaries WHERE log_date >= ?1 AND log_date <= ?2",
+        params![month.start_date, month.end_date],
+        |row| row.get(0),
+    ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let mut stmt = conn.prepare(
+        "SELECT log_date FROM daily_summaries WHERE log_date >= ?1 AND log_date <= ?2
+         UNION
+         SELECT log_date FROM symptom_logs WHERE log_date >= ?1 AND log_date <= ?2
+         ORDER BY log_date DESC",
+    ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let dates: Vec<String> = stmt.query_map(params![week.start_date, week.end_date], |row| row.get(0))
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let mut recent_days = Vec::new();
+    for date in dates {
+        recent_days.push(symptom_day(conn, date)?);
+    }
+
+    Ok(CurrentSummary {
+        as_of: week.end_date,
+        latest_lab_session,
+        abnormal_labs: abnormal_labs(conn)?,
+        mean_wellness_30d,
+        recent_days,
+    })
+}
+
+fn read_tracker_resource(conn: &Connection, policy: &McpPolicy, uri: &str) -> Result<ReadResourceResult, McpError> {
+    let path = uri.strip_prefix("tracker://").ok_or_else(|| resource_not_found(uri))?;
+    let segments: Vec<&str> = path.split('/').collect();
+    if !resource_allowed(policy, &segments) {
+        return Err(policy_blocked(uri));
+    }
+    let json = match segments.as_slice() {
+        ["labs", "session", id] => {
+            let id: i64 = id.parse().map_err(|_| resource_not_found(uri))?;
+            serde_json::to_string_pretty(&read_lab_session(conn, uri, id)?)
+        }
+        ["symptoms", "day", date] => serde_json::to_string_pretty(&read_symptom_day(conn, uri, date)?),
+        ["tests", name, "history"] => {
+            let name = percent_decode(name).ok_or_else(|| {
+                McpError::invalid_params(format!("Invalid percent-encoding in {}", uri), None)
+            })?;
+            serde_json::to_string_pretty(&read_test_history(conn, uri, name)?)
+        }
+        ["summary", "current"] => serde_json::to_string_pretty(&read_current_summary(conn)?),
+        _ => return Err(resource_not_found(uri)),
+    }
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContents::TextResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some(RESOURCE_MIME_TYPE.to_string()),
+            text: json,
+            meta: None,
+        }],
+    })
+}
+
+// Prompts
+const PROMPT_APPOINTMENT: &str = "prepare_rheumatology_appointment";
+const PROMPT_ABNORMAL_LABS: &str = "explain_abnormal_labs";
+const PROMPT_SYMPTOM_SUMMARY: &str = "summarize_symptoms";
+
+#[derive(Debug, Serialize)]
+struct AbnormalWithPrevious {
+    #[serde(flatten)]
+    latest: AbnormalResult,
+    previous_value: Option<f64>,
+    previous_text_value: Option<String>,
+    previous_flag: Option<String>,
+    previous_date: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PanelLabRow {
+    test_date: String,
+    #[serde(flatten)]
+    result: LabResultRow,
+}
+
+fn prompt_argument(name: &str, description: &str) -> PromptArgument {
+    PromptArgument {
+        name: name.to_string(),
+        title: None,
+        description: Some(description.to_string()),
+        required: Some(false),
+    }
+}
+
+fn range_arguments() -> Vec<PromptArgument> {
+    vec![
+        prompt_argument("days", "Number of days to look back from end_date (or today)"),
+        prompt_argument("start_date", "Start date (YYYY-MM-DD), inclusive"),
+        prompt_argument("end_date", "End date (YYYY-MM-DD), inclusive. Defaults to today"),
+    ]
+}
+
+fn panels_argument() -> PromptArgument {
+    prompt_argument("panels", "Comma-separated lab panels to include (e.g. \"CBC, Inflammatory Markers\"). Defaults to all panels")
+}
+
+fn prompt_allowed(policy: &McpPolicy, name: &str) -> bool {
+    match name {
+        PROMPT_SYMPTOM_SUMMARY => policy.allows_category(CATEGORY_SYMPTOMS),
+        _ => policy.allows_category(CATEGORY_LABS),
+    }
+}
+
+fn tracker_prompts() -> Vec<Prompt> {
+    let mut appointment_args = range_arguments();
+    appointment_args.push(panels_argument());
+    vec![
+        Prompt::new(
+            PROMPT_APPOINTMENT,
+            Some("Prepare for a rheumatology appointment: abnormal labs with previous values, labs drawn in the range, monthly symptom rollups and flares. Defaults to the last 90 days"),
+            Some(appointment_args),
+        ),
+        Prompt::new(
+            PROMPT_ABNORMAL_LABS,
+            Some("Explain the latest abnormal lab values, each with its previous result for comparison"),
+            Some(vec![panels_argument()]),
+        ),
+        Prompt::new(
+            PROMPT_SYMPTOM_SUMMARY,
+            Some("Summarise symptoms and wellness week by week. Defaults to the last 30 days"),
+            Some(range_arguments()),
+        ),
+    ]
+}
+
+/// Reads a prompt argument as a string. Clients send prompt arguments as
+/// strings, but numbers are accepted for `days`.
+fn prompt_arg(args: &JsonObject, name: &str) -> Option<String> {
+    match args.get(name)? {
+        serde_json::Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Resolves the prompt's date range, falling back to the last `default_days`
+/// when neither `days` nor `start_date` is given.
+fn prompt_range(conn: &Connection, args: &JsonObject, default_days: i64) -> Result<DateRange, McpError> {
+    let days = prompt_arg(args, "days")
+        .map(|d| d.parse::<i64>().map_err(|_| McpError::invalid_params(format!("Invalid days: {}", d), None)))
+        .transpose()?;
+    let start_date = prompt_arg(args, "start_date");
+    let days = if days.is_none() && start_date.is_none() { Some(default_days) } else { days };
+    resolve_range(conn, days, start_date, prompt_arg(args, "end_date"))
+}
+
+/// Lower-cased panel names from the comma-separated `panels` argument; empty
+/// means all panels.
+fn prompt_panels(args: &JsonObject) -> Vec<String> {
+    prompt_arg(args, "panels")
+        .map(|p| {
+            p.split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn panel_selected(panels: &[String], panel: &str) -> bool {
+    panels.is_empty() || panels.contains(&panel.to_lowercase())
+}
+
+/// Abnormal labs in the selected panels, each with the test's previous result.
+fn abnormal_with_previous(conn: &Connection, panels: &[String]) -> Result<Vec<AbnormalWithPrevious>, McpError> {
+    let mut stmt = conn.prepare(
+        "SELECT r.value, r.text_value, r.flag, s.test_date
+         FROM lab_results r
+         JOIN lab_sessions s ON r.session_id = s.id
+         WHERE r.test_name = ?1 AND s.test_date < ?2
+         ORDER BY s.test_date DESC
+         LIMIT 1",
+    ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let mut output = Vec::new();
+    for latest in abnormal_labs(conn)? {
+        if !panel_selected(panels, &latest.panel) {
+            continue;
+        }
+        let previous: Option<(Option<f64>, String, String, String)> = stmt
+            .query_row(params![latest.test_name, latest.test_date], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .ok();
+        let (previous_value, previous_text_value, previous_flag, previous_date) = match previous {
+            Some((value, text, flag, date)) => (value, Some(text), Some(flag), Some(date)),
+            None => (None, None, None, None),
+        };
+        output.push(AbnormalWithPrevious {
+            latest,
+            previous_value,
+            previous_text_value,
+            previous_flag,
+            previous_date,
+        });
+    }
+    Ok(output)
+}
+
+/// Every lab result drawn in `range` within the selected panels.
+fn panel_labs(conn: &Connection, range: &DateRange, panels: &[String]) -> Result<Vec<PanelLabRow>, McpError> {
+    let mut stmt = conn.prepare(
+        "SELECT s.test_date, r.test_name, r.panel, r.value, r.text_value, r.unit,
+                r.ref_range_low, r.ref_range_high, r.flag
+         FROM lab_results r
+         JOIN lab_sessions s ON r.session_id = s.id
+         WHERE s.test_date >= ?1 AND s.test_date <= ?2
+         ORDER BY s.test_date, r.panel, r.test_name",
+    ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    let rows = stmt.query_map(params![range.start_date, range.end_date], |row| {
+        Ok(PanelLabRow {
+            test_date: row.get(0)?,
+            result: LabResultRow {
+                test_name: row.get(1)?,
+                panel: row.get(2)?,
+                value: row.get(3)?,
+                text_value: row.get(4)?,
+                unit: row.get(5)?,
+                ref_range_low: row.get(6)?,
+                ref_range_high: row.get(7)?,
+                flag: row.get(8)?,
+            },
+        })
+    }).map_err(|e| McpError::internal_error(e.to_string(), None))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    Ok(rows.into_iter().filter(|r| panel_selected(panels, &r.result.panel)).collect())
+}
+
+fn data_message(title: &str, data: &impl Serialize) -> Result<PromptMessage, McpError> {
+    let json = serde_json::to_string_pretty(data)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    Ok(PromptMessage::new_text(
+        PromptMessageRole::User,
+        format!("{}:\n```json\n{}\n```", title, json),
+    ))
+}
+
+/// Builds a prompt's messages. The appointment prompt needs labs; its symptom
+/// rollup and flares are left out when those categories are blocked.
+fn build_prompt(conn: &Connection, name: &str, args: &JsonObject) -> Result<GetPromptResult, McpError> {
+    let policy = load_policy(conn)?;
+    if !prompt_allowed(&policy, name) {
+        return Err(policy_blocked(&format!("Prompt {}", name)));
+    }
+    let mut messages = Vec::new();
+    let (description, request) = match name {
+        PROMPT_APPOINTMENT => {
+            let range = prompt_range(conn, args, 90)?;
+            let panels = prompt_panels(args);
+            messages.push(data_message(
+                "My latest abnormal lab values, each with the previous result",
+                &abnormal_with_previous(conn, &panels)?,
+            )?);
+            messages.push(data_message(
+                &format!("Labs drawn from {} to {}", range.start_date, range.end_date),
+                &panel_labs(conn, &range, &panels)?,
+            )?);
+            if policy.allows_category(CATEGORY_SYMPTOMS) {
+                let rollup = rollups::query_rollups(conn, RollupPeriod::Month, &range)
+                    .map_err(|e| McpError::internal_error(e, None))?;
+                messages.push(data_message("Monthly symptom and wellness rollup", &rollup)?);
+            }
+            if policy.allows_category(CATEGORY_FLARES) {
+                let flares = query_flares_with_labs(conn, &range.start_date, &range.end_date)
+                    .map_err(|e| McpError::internal_error(e, None))?;
+                messages.push(data_message("Flare episodes in this period", &flares)?);
+            }
+            (
+                format!("Rheumatology appointment preparation for {} to {}", range.start_date, range.end_date),
+                "I have a rheumatology appointment coming up. Using the data above, \
+                 summarise how I have been doing over this period: notable lab changes, \
+                 symptom patterns and flares. Then suggest the questions I should ask \
+                 my rheumatologist and anything I should make sure to mention.",
+            )
+        }
+        PROMPT_ABNORMAL_LABS => {
+            let panels = prompt_panels(args);
+            messages.push(data_message(
+                "My latest abnormal lab values, each with the previous result",
+                &abnormal_with_previous(conn, &panels)?,
+            )?);
+            (
+                "Explanation of the latest abnormal labs".to_string(),
+                "Explain each of these abnormal lab results in plain language: what the \
+                 test measures, what a value outside the reference range can indicate, \
+                 and whether it has improved or worsened since the previous result. \
+                 Note which results might be worth discussing with my doctor.",
+            )
+        }
+        PROMPT_SYMPTOM_SUMMARY => {
+            let range = prompt_range(conn, args, 30)?;
+            let rollup = rollups::query_rollups(conn, RollupPeriod::Week, &range)
+                .map_err(|e| McpError::internal_error(e, None))?;
+            messages.push(data_message("Weekly symptom and wellness rollup", &rollup)?);
+            (
+                format!("Symptom summary for {} to {}", range.start_date, range.end_date),
+                "Summarise my symptoms over this period: which symptoms were most \
+                 frequent and severe, how my wellness score changed week to week, and \
+                 any trends or patterns worth noting.",
+            )
+        }
+        _ => {
+            return Err(McpError::invalid_params(format!("Unknown prompt: {}", name), None));
+        }
+    };
+    messages.push(PromptMessage::new_text(PromptMessageRole::User, request));
+    Ok(GetPromptResult {
+        description: Some(description),
+        messages,
+    })
+}
+
+// Access log
+fn client_name(context: &RequestContext<RoleServer>) -> String {
+    context.peer.peer_info().map(|info| info.client_info.name.clone()).unwrap_or_default()
+}
+
+/// Records in a JSON response: the length of a top-level list or of its
+/// `results`.
+fn json_row_count(value: &serde_json::Value) -> Option<i64> {
+    match value {
+        serde_json::Value::Array(items) => Some(items.len() as i64),
+        serde_json::Value::Object(fields) => fields.get("results")?.as_array().map(|items| items.len() as i64),
+        _ => None,
+    }
+}
+
+/// Appends to the sidecar access log. Failures are only traced so logging
+/// never breaks a request.
+fn log_access(
+    client: &str,
+    kind: &str,
+    name: &str,
+    params: Option<&JsonObject>,
+    row_count: Option<i64>,
+    error: Option<&McpError>,
+) {
+    let params = params
+        .filter(|params| !params.is_empty())
+        .and_then(|params| serde_json::to_string(params).ok())
+        .unwrap_or_default();
+    let error = error.map(|e| e.message.to_string());
+    let access = NewAccess { client, kind, name, params: &params, row_count, error: error.as_deref() };
+    let log = match mcp_access_log::open(&mcp_access_log::path_for(&db_path())) {
+        Ok(log) => log,
+        Err(e) => {
+            tracing::warn!("Failed to open MCP access log: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = mcp_access_log::record(&log, &access) {
+        tracing::warn!("Failed to write MCP access log: {}", e);
+    }
+    if let Err(e) = prune_access_log(&log) {
+        tracing::warn!("Failed to prune MCP access log: {}", e);
+    }
+}
+
+/// How often a running server drops log entries past the retention set in
+/// the app; an HTTP server may stay up for weeks.
+const ACCESS_LOG_PRUNE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+static LAST_ACCESS_LOG_PRUNE: Mutex<Option<Instant>> = Mutex::new(None);
+
+fn prune_access_log(log: &Connection) -> Result<(), String> {
+    {
+        let mut last = LAST_ACCESS_LOG_PRUNE.lock().unwrap_or_else(|e| e.into_inner());
+        if last.is_some_and(|at| at.elapsed() < ACCESS_LOG_PRUNE_INTERVAL) {
+            return Ok(());
+        }
+        *last = Some(Instant::now());
+    }
+    let tracker = Connection::open_with_flags(db_path(), rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| e.to_string())?;
+    mcp_access_log::prune(log, mcp_access_log::retention_days(&tracker))
+}
+
+#[derive(Clone)]
+pub struct TrackerMcp {
+    tool_router: ToolRouter<TrackerMcp>,
+}
+
+impl Default for TrackerMcp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[tool_router]
+impl TrackerMcp {
+    pub fn new() -> Self {
+        Self {
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    #[tool(description = "Get lab results for a date range (or the last N days), grouped by session, newest first. Paged: pass next_cursor back as cursor for more", output_schema = page_output_schema::<LabSessionResult>())]
+    fn get_recent_labs(
+        &self,
+        Parameters(param): Parameters<PagedRangeParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let page = PageRequest::resolve(&conn, param)?;
+        let total: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM lab_sessions WHERE test_date >= ?1 AND test_date <= ?2",
+            params![page.range.start_date, page.range.end_date],
+            |row| row.get(0),
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let mut stmt = conn.prepare(
+            "SELECT id, test_date, lab_name, notes FROM lab_sessions
+             WHERE test_date >= ?1 AND test_date <= ?2
+               AND (?3 IS NULL OR test_date < ?3 OR (test_date = ?3 AND id < ?4))
+             ORDER BY test_date DESC, id DESC
+             LIMIT ?5",
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        let mut sessions: Vec<(i64, String, String, String)> = stmt.query_map(
+            params![page.range.start_date, page.range.end_date, page.after_date(), page.after_id(), page.fetch_limit()],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let next_cursor = page.next_cursor(&mut sessions, |(id, test_date, ..)| (test_date.clone(), Some(*id)));
+
+        let mut output = Vec::new();
+        for (id, test_date, lab_name, notes) in sessions {
+            let results = session_results(&conn, id)?;
+            output.push(LabSessionResult { id, test_date, lab_name, notes, results });
+        }
+
+        let result_count: usize = output.iter().map(|session| session.results.len()).sum();
+        let summary = format!(
+            "{} with {} from {}",
+            page_text(output.len(), total, "lab session", "lab sessions"),
+            counted(result_count, "result", "results"),
+            range_text(&page.range),
+        );
+        paged_result(&page.page(total, next_cursor, output), &page.omit_fields, summary)
+    }
+
+    #[tool(description = "Get all currently flagged abnormal lab values (latest result per test where flag is not normal)", output_schema = output_schema::<ListOut<AbnormalResult>>())]
+    fn get_abnormal_labs(&self) -> Result<CallToolResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let results = abnormal_labs(&conn)?;
+
+        let flagged: Vec<String> = results.iter().map(|r| format!("{} {}", r.test_name, r.flag)).collect();
+        let summary = if flagged.is_empty() {
+            "No abnormal lab values".to_string()
+        } else {
+            format!("{}: {}", counted(flagged.len(), "abnormal lab value", "abnormal lab values"), flagged.join(", "))
+        };
+        structured_result(&ListOut { results }, summary)
+    }
+
+    #[tool(description = "List every lab test name with results, with its panel and unit (from the latest result), the number of results and the last test date. Use the exact names with get_trends", output_schema = output_schema::<ListOut<TestNameOut>>())]
+    fn list_test_names(&self) -> Result<CallToolResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        // SQLite takes the bare panel and unit from the row with MAX(test_date)
+        let mut stmt = conn.prepare(
+            "SELECT r.test_name, r.panel, r.unit, COUNT(*), MAX(s.test_date)
+             FROM lab_results r
+             JOIN lab_sessions s ON r.session_id = s.id
+             GROUP BY r.test_name
+             ORDER BY r.panel, r.test_name",
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let tests: Vec<TestNameOut> = stmt.query_map([], |row| {
+            Ok(TestNameOut {
+                test_name: row.get(0)?,
+                panel: row.get(1)?,
+                unit: row.get(2)?,
+                count: row.get(3)?,
+                last_date: row.get(4)?,
+            })
+        }).map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        let summary = counted(tests.len(), "lab test", "lab tests");
+        structured_result(&ListOut { results: tests }, summary)
+    }
+
+    #[tool(description = "List the symptoms the user tracks, with category, description and whether each is still active. Use the exact names with log_symptoms and get_lab_symptom_correlation", output_schema = output_schema::<ListOut<SymptomOut>>())]
+    fn list_symptoms(&self) -> Result<CallToolResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let mut stmt = conn.prepare(
+            "SELECT name, category, active, description FROM symptoms ORDER BY sort_order, name",
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let symptoms: Vec<SymptomOut> = stmt.query_map([], |row| {
+            Ok(SymptomOut {
+                name: row.get(0)?,
+                category: row.get(1)?,
+                active: row.get(2)?,
+                description: row.get(3)?,
+            })
+        }).map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        let active = symptoms.iter().filter(|s| s.active).count();
+        let summary = format!("{}, {} active", counted(symptoms.len(), "symptom", "symptoms"), active);
+        structured_result(&ListOut { results: symptoms }, summary)
+    }
+
+    #[tool(description = "List the user's custom lab test definitions with panel, unit, reference range, a plain-language description and clinical notes, for explaining tests to the user", output_schema = output_schema::<ListOut<CustomTestOut>>())]
+    fn list_custom_tests(&self) -> Result<CallToolResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let mut stmt = conn.prepare(
+            "SELECT name, panel, unit, ref_low, ref_high, text_only, description, clinical
+             FROM custom_lab_tests ORDER BY panel, name",
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let tests: Vec<CustomTestOut> = stmt.query_map([], |row| {
+            Ok(CustomTestOut {
+                name: row.get(0)?,
+                panel: row.get(1)?,
+                unit: row.get(2)?,
+                ref_low: row.get(3)?,
+                ref_high: row.get(4)?,
+                text_only: row.get(5)?,
+                description: row.get(6)?,
+                clinical: row.get(7)?,
+            })
+        }).map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        let summary = counted(tests.len(), "custom test", "custom tests");
+        structured_result(&ListOut { results: tests }, summary)
+    }
+
+    #[tool(description = "Get symptom logs with severity for a date range (or the last N days), one item per logged day, newest first. Paged: pass next_cursor back as cursor for more", output_schema = page_output_schema::<SymptomDay>())]
+    fn get_symptom_history(
+        &self,
+        Parameters(param): Parameters<PagedRangeParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let page = PageRequest::resolve(&conn, param)?;
+        let total: i64 = conn.query_row(
+            "SELECT COUNT(DISTINCT log_date) FROM symptom_logs WHERE log_date >= ?1 AND log_date <= ?2",
+            params![page.range.start_date, page.range.end_date],
+            |row| row.get(0),
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let mut dstmt = conn.prepare(
+            "SELECT DISTINCT log_date FROM symptom_logs
+             WHERE log_date >= ?1 AND log_date <= ?2
+               AND (?3 IS NULL OR log_date < ?3)
+             ORDER BY log_date DESC
+             LIMIT ?4",
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        let mut dates: Vec<String> = dstmt
+            .query_map(
+                params![page.range.start_date, page.range.end_date, page.after_date(), page.fetch_limit()],
+                |row| row.get(0),
+            )
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let next_cursor = page.next_cursor(&mut dates, |date| (date.clone(), None));
+
+        let mut output = Vec::new();
+        for date in dates {
+            output.push(symptom_day(&conn, date)?);
+        }
+
+        let summary = format!(
+            "{} with symptom logs from {}",
+            page_text(output.len(), total, "day", "days"),
+            range_text(&page.range),
+        );
+        paged_result(&page.page(total, next_cursor, output), &page.omit_fields, summary)
+    }
+
+    #[tool(description = "Get time series data for a specific lab test over a date range (or the last N days)", output_schema = output_schema::<RangedOut<Vec<TrendPointOut>>>())]
+    fn get_trends(
+        &self,
+        Parameters(TrendsParam { test_name, range: RangeParam { days, start_date, end_date } }): Parameters<TrendsParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let range = resolve_range(&conn, days, start_date, end_date)?;
+        let mut stmt = conn.prepare(
+            "SELECT s.test_date, r.value, r.text_value, r.flag
+             FROM lab_results r
+             JOIN lab_sessions s ON r.session_id = s.id
+             WHERE r.test_name = ?1
+               AND s.test_date >= ?2 AND s.test_date <= ?3
+             ORDER BY s.test_date ASC",
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        let points: Vec<TrendPointOut> = stmt.query_map(params![test_name, range.start_date, range.end_date], |row| {
+            Ok(TrendPointOut {
+                test_date: row.get(0)?,
+                value: row.get(1)?,
+                text_value: row.get(2)?,
+                flag: row.get(3)?,
+            })
+        }).map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        let mut summary = format!(
+            "{} for {} from {}",
+            counted(points.len(), "result", "results"),
+            test_name,
+            range_text(&range),
+        );
+        if let Some(latest) = points.last() {
+            let value = latest.value.map(|v| v.to_string()).unwrap_or_else(|| latest.text_value.clone());
+            summary.push_str(&format!("; latest {} ({}) on {}", value, latest.flag, latest.test_date));
+        }
+        structured_result(&RangedOut::new(range, points), summary)
+    }
+
+    #[tool(description = "Get daily wellness scores and notes for a date range (or the last N days), newest first. Paged: pass next_cursor back as cursor for more", output_schema = page_output_schema::<DailySummaryOut>())]
+    fn get_daily_summaries(
+        &self,
+        Parameters(param): Parameters<PagedRangeParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let page = PageRequest::resolve(&conn, param)?;
+        let total: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM daily_summaries WHERE log_date >= ?1 AND log_date <= ?2",
+            params![page.range.start_date, page.range.end_date],
+            |row| row.get(0),
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let mut stmt = conn.prepare(
+            "SELECT log_date, wellness_score, notes FROM daily_summaries
+             WHERE log_date >= ?1 AND log_date <= ?2
+               AND (?3 IS NULL OR log_date < ?3)
+             ORDER BY log_date DESC
+             LIMIT ?4",
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        let mut summaries: Vec<DailySummaryOut> = stmt.query_map(
+            params![page.range.start_date, page.range.end_date, page.after_date(), page.fetch_limit()],
+            |row| {
+                Ok(DailySummaryOut {
+                    date: row.get(0)?,
+                    wellness_score: row.get(1)?,
+                    notes: row.get(2)?,
+                })
+            },
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let next_cursor = page.next_cursor(&mut summaries, |day| (day.date.clone(), None));
+
+        let mut summary = format!(
+            "{} from {}",
+            page_text(summaries.len(), total, "daily summary", "daily summaries"),
+            range_text(&page.range),
+        );
+        if !summaries.is_empty() {
+            let mean = summaries.iter().map(|d| d.wellness_score as f64).sum::<f64>() / summaries.len() as f64;
+            summary.push_str(&format!("; mean wellness {:.1}", mean));
+        }
+        paged_result(&page.page(total, next_cursor, summaries), &page.omit_fields, summary)
+    }
+
+    #[tool(description = "Get an INR report for a date range (or the last N days): time in therapeutic range (Rosendaal method), out-of-range streaks and the warfarin weekly dose log", output_schema = output_schema::<InrReport>())]
+    fn get_inr_report(
+        &self,
+        Parameters(RangeParam { days, start_date, end_date }): Parameters<RangeParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let range = resolve_range(&conn, days, start_date, end_date)?;
+        let report = build_inr_report(&conn, &range.start_date, &range.end_date)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let mut summary = format!(
+            "{} from {} to {}",
+            counted(report.points.len(), "INR result", "INR results"),
+            report.start_date,
+            report.end_date,
+        );
+        if let Some(ttr) = report.time_in_range.ttr_percent {
+            summary.push_str(&format!("; {:.0}% time in range", ttr));
+        }
+        structured_result(&report, summary)
+    }
+
+    #[tool(description = "Get vital signs (blood pressure, heart rate, temperature, weight, SpO2) for a date range (or the last N days), with out-of-range flags", output_schema = output_schema::<RangedOut<Vec<VitalReading>>>())]
+    fn get_vitals(
+        &self,
+        Parameters(RangeParam { days, start_date, end_date }): Parameters<RangeParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let range = resolve_range(&conn, days, start_date, end_date)?;
+        let readings = vitals::query_readings(&conn, &range.start_date, &range.end_date)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let flagged = readings.iter().filter(|r| !r.flags.is_empty()).count();
+        let summary = format!(
+            "{} from {}, {} out of range",
+            counted(readings.len(), "vital reading", "vital readings"),
+            range_text(&range),
+            flagged,
+        );
+        structured_result(&RangedOut::new(range, readings), summary)
+    }
+
+    #[tool(description = "Get flare episodes overlapping a date range (or the last N days), with affected systems, triggers, treatment notes and the labs drawn during each flare", output_schema = output_schema::<RangedOut<Vec<FlareWithLabs>>>())]
+    fn get_flares(
+        &self,
+        Parameters(RangeParam { days, start_date, end_date }): Parameters<RangeParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let range = resolve_range(&conn, days, start_date, end_date)?;
+        let flares = query_flares_with_labs(&conn, &range.start_date, &range.end_date)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let summary = format!("{} overlapping {}", counted(flares.len(), "flare", "flares"), range_text(&range));
+        structured_result(&RangedOut::new(range, flares), summary)
+    }
+
+    #[tool(description = "Correlate a lab test with a symptom's severity (or the wellness score) averaged over a window around each draw. Returns Pearson and Spearman coefficients, sample size and the aligned pairs", output_schema = output_schema::<CorrelationResult>())]
+    fn get_lab_symptom_correlation(
+        &self,
+        Parameters(CorrelationParam { test_name, symptom, window_days, range: RangeParam { days, start_date, end_date } }): Parameters<CorrelationParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let window_days = window_days.unwrap_or(3);
+        analysis::validate_window_days(window_days).map_err(|e| McpError::invalid_params(e, None))?;
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let range = resolve_range(&conn, days, start_date, end_date)?;
+        let target = match symptom {
+            Some(name) => SymptomTarget::Symptom(
+                analysis::symptom_id_by_name(&conn, &name)
+                    .map_err(|e| McpError::invalid_params(e, None))?,
+            ),
+            None => SymptomTarget::Wellness,
+        };
+        let result = analysis::lab_symptom_correlation(&conn, &test_name, target, window_days, &range)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let coefficient = |c: Option<f64>| c.map(|c| format!("{:.2}", c)).unwrap_or_else(|| "n/a".to_string());
+        let summary = format!(
+            "{} vs {}: n = {}, Pearson {}, Spearman {}",
+            result.test_name,
+            result.target,
+            result.n,
+            coefficient(result.pearson),
+            coefficient(result.spearman),
+        );
+        structured_result(&result, summary)
+    }
+
+    #[tool(description = "Summarise symptom logs and wellness by ISO week or calendar month: per-symptom days present, frequency, mean and max severity, and mean wellness per bucket", output_schema = output_schema::<Rollup>())]
+    fn get_rollups(
+        &self,
+        Parameters(RollupParam { period, range: RangeParam { days, start_date, end_date } }): Parameters<RollupParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let period = RollupPeriod::parse(&period).map_err(|e| McpError::invalid_params(e, None))?;
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let range = resolve_range(&conn, days, start_date, end_date)?;
+        let rollup = rollups::query_rollups(&conn, period, &range)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let summary = format!(
+            "{} {} buckets from {} to {}",
+            rollup.buckets.len(),
+            rollup.period,
+            rollup.start_date,
+            rollup.end_date,
+        );
+        structured_result(&rollup, summary)
+    }
+
+    #[tool(description = "Full-text search across lab session notes, symptom notes and daily notes. Returns each hit's source, date, context (lab or symptom name) and a snippet with matches wrapped in **", output_schema = output_schema::<ListOut<NoteHit>>())]
+    fn search_notes(
+        &self,
+        Parameters(SearchParam { query, limit }): Parameters<SearchParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let hits = search::search_notes(&conn, &query, limit)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let summary = format!("{} matching \"{}\"", counted(hits.len(), "note", "notes"), query);
+        structured_result(&ListOut { results: hits }, summary)
+    }
+
+    #[tool(description = "Log symptoms for a day (default today). Merges with what is already logged that day. Requires MCP write access to be enabled in the app; every change is recorded there for review and undo", output_schema = output_schema::<WriteOut>())]
+    fn log_symptoms(
+        &self,
+        Parameters(LogSymptomsParam { date, symptoms: inputs, wellness }): Parameters<LogSymptomsParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let conn = open_db_for_write()?;
+        check_write_enabled(&conn)?;
+        if inputs.is_empty() {
+            return Err(McpError::invalid_params("Provide at least one symptom".to_string(), None));
+        }
+        let date = match date {
+            Some(date) => date,
+            None => resolve_range(&conn, Some(0), None, None)?.end_date,
+        };
+        let policy = load_policy(&conn)?;
+        check_write_date(&conn, &policy, &date)?;
+
+        let tx = conn.unchecked_transaction()
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let previous = symptoms::day_snapshot(&tx, &date)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+        let mut entries = previous.as_ref().map(|day| day.entries.clone()).unwrap_or_default();
+        let mut logged = Vec::new();
+        for input in inputs {
+            let unknown = || McpError::invalid_params(format!("Unknown symptom: {}", input.symptom), None);
+            let (symptom_id, name, category): (i64, String, String) = tx.query_row(
+                "SELECT id, name, category FROM symptoms WHERE name = ?1 COLLATE NOCASE AND active = 1",
+                params![input.symptom.trim()],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            ).map_err(|_| unknown())?;
+            if policy.hides_symptom_category(&category) {
+                return Err(unknown());
+            }
+            match entries.iter_mut().find(|e| e.symptom_id == symptom_id) {
+                Some(entry) => {
+                    entry.severity = input.severity;
+                    entry.intraday = None;
+                    if let Some(notes) = input.notes {
+                        entry.notes = notes;
+                    }
+                }
+                None => entries.push(SymptomEntry {
+                    symptom_id,
+                    severity: input.severity,
+                    notes: input.notes.unwrap_or_default(),
+                    intraday: None,
+                    body_locations: None,
+                    duration_minutes: None,
+                    onset_time: None,
+                }),
+            }
+            logged.push((symptom_id, name, input.severity));
+        }
+        let wellness = wellness
+            .or(previous.as_ref().map(|day| day.wellness))
+            .unwrap_or(symptoms::DEFAULT_WELLNESS);
+        let notes = previous.as_ref().map(|day| day.notes.clone()).unwrap_or_default();
+        symptoms::save_day(&tx, &date, &entries, wellness, &notes, SOURCE_MCP)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        // Symptoms with intraday readings keep them, and the readings set the
+        // day's severity, so report what was stored
+        let mut logged_text = Vec::new();
+        for (symptom_id, name, requested) in logged {
+            let stored: i64 = tx.query_row(
+                "SELECT severity FROM symptom_logs WHERE log_date = ?1 AND symptom_id = ?2",
+                params![date, symptom_id],
+                |row| row.get(0),
+            ).unwrap_or(0);
+            if stored == requested {
+                logged_text.push(format!("{} {}", name, stored));
+            } else {
+                logged_text.push(format!("{} {} (from intraday readings)", name, stored));
+            }
+        }
+        let summary = format!("Logged {} on {}", logged_text.join(", "), date);
+        let write_id = mcp_writes::record(&tx, "log_symptoms", &summary, &date, &WriteTarget::Day { previous })
+            .map_err(|e| McpError::internal_error(e, None))?;
+        tx.commit().map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        structured_result(&WriteOut { write_id, summary: summary.clone(), session_id: None }, summary)
+    }
+
+    #[tool(description = "Add a lab session with its results. Requires MCP write access to be enabled in the app; every change is recorded there for review and undo", output_schema = output_schema::<WriteOut>())]
+    fn add_lab_session(
+        &self,
+        Parameters(AddLabSessionParam { test_date, lab_name, notes, results: inputs }): Parameters<AddLabSessionParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let conn = open_db_for_write()?;
+        check_write_enabled(&conn)?;
+        let policy = load_policy(&conn)?;
+        check_write_date(&conn, &policy, &test_date)?;
+
+        let tx = conn.unchecked_transaction()
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let mut previous_stmt = tx.prepare(
+            "SELECT r.panel, r.unit, r.ref_range_low, r.ref_range_high
+             FROM lab_results r
+             JOIN lab_sessions s ON r.session_id = s.id
+             WHERE r.test_name = ?1 AND s.test_date <= ?2
+             ORDER BY s.test_date DESC
+             LIMIT 1",
+        ).map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let mut results = Vec::new();
+        for input in inputs {
+            let previous: Option<(String, String, Option<f64>, Option<f64>)> = previous_stmt
+                .query_row(params![input.test_name, test_date], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                })
+                .ok();
+            let (prev_panel, prev_unit, prev_low, prev_high) = previous.unwrap_or_default();
+            // A test last recorded under a hidden panel stays hidden, and so
+            // does its stored unit and range
+            let panel = input.panel.unwrap_or(prev_panel);
+            if policy.hides_panel(&panel) {
+                return Err(policy_blocked(&format!("The {} panel", panel)));
+            }
+            let ref_range_low = input.ref_range_low.or(prev_low);
+            let ref_range_high = input.ref_range_high.or(prev_high);
+            let flag = input.flag.unwrap_or_else(|| {
+                labs::auto_flag(input.value, ref_range_low, ref_range_high).to_string()
+            });
+            results.push(LabResult {
+                id: None,
+                session_id: None,
+                test_name: input.test_name,
+                panel,
+                value: input.value,
+                text_value: input.text_value.unwrap_or_default(),
+                unit: input.unit.unwrap_or(prev_unit),
+                ref_range_low,
+                ref_range_high,
+                flag,
+            });
+        }
+        drop(previous_stmt);
+        let count = results.iter().filter(|r| r.value.is_some() || !r.text_value.is_empty()).count();
+        if count == 0 {
+            return Err(McpError::invalid_params(
+                "Provide at least one result with a value or text value".to_string(),
+                None,
+            ));
+        }
+
+        let session = LabSession {
+            id: None,
+            test_date,
+            lab_name: lab_name.unwrap_or_default(),
+            notes: notes.unwrap_or_default(),
+        };
+        let session_id = labs::save_session(&tx, &session, &results, SOURCE_MCP)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+        let summary = format!("Added lab session on {} with {} result(s)", session.test_date, count);
+        let write_id = mcp_writes::record(
+            &tx,
+            "add_lab_session",
+            &summary,
+            &session.test_date,
+            &WriteTarget::LabSession(session_id),
+        ).map_err(|e| McpError::internal_error(e, None))?;
+        tx.commit().map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        structured_result(&WriteOut { write_id, summary: summary.clone(), session_id: Some(session_id) }, summary)
+    }
+
+    #[tool(description = "Append a note to a day's daily notes (default today), optionally setting the wellness score. Requires MCP write access to be enabled in the app; every change is recorded there for review and undo", output_schema = output_schema::<WriteOut>())]
+    fn add_daily_note(
+        &self,
+        Parameters(DailyNoteParam { date, note, wellness }): Parameters<DailyNoteParam>,
+    ) -> Result<CallToolResult, McpError> {
+        let conn = open_db_for_write()?;
+        check_write_enabled(&conn)?;
+        let note = note.trim();
+        if note.is_empty() {
+            return Err(McpError::invalid_params("Note must not be empty".to_string(), None));
+        }
+        let date = match date {
+            Some(date) => date,
+            None => resolve_range(&conn, Some(0), None, None)?.end_date,
+        };
+        check_write_date(&conn, &load_policy(&conn)?, &date)?;
+
+        let tx = conn.unchecked_transaction()
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let previous = symptoms::day_snapshot(&tx, &date)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+        let notes = match previous.as_ref().map(|day| day.notes.as_str()) {
+            Some(existing) if !existing.is_empty() => format!("{}\n{}", existing, note),
+            _ => note.to_string(),
+        };
+        let score = wellness
+            .or(previous.as_ref().map(|day| day.wellness))
+            .unwrap_or(symptoms::DEFAULT_WELLNESS);
+        symptoms::save_daily_summary(&tx, &date, score, &notes, SOURCE_MCP)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let summary = match wellness {
+            Some(score) => format!("Added a daily note on {} and set wellness to {}", date, score),
+            None => format!("Added a daily note on {}", date),
+        };
+        let write_id = mcp_writes::record(&tx, "add_daily_note", &summary, &date, &WriteTarget::Day { previous })
+            .map_err(|e| McpError::internal_error(e, None))?;
+        tx.commit().map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        structured_result(&WriteOut { write_id, summary: summary.clone(), session_id: None }, summary)
+    }
+}
+
+// Tool listing and dispatch are written out rather than generated by
+// #[tool_handler] so the access policy can hide and block tools.
+impl ServerHandler for TrackerMcp {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: ProtocolVersion::V_2024_11_05,
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .enable_prompts()
+                .build(),
+            server_info: Implementation::from_build_env(),
+            instructions: Some(
+                "MCP server for querying symptom and lab test tracking data. \
+                 Provides tools to retrieve recent labs, abnormal values, symptom history, \
+                 trends for specific tests, daily wellness summaries, vital signs, \
+                 flare episodes, INR anticoagulation reports, weekly/monthly \
+                 rollups, full-text note search, and lab–symptom correlation \
+                 analysis. list_test_names, list_symptoms and list_custom_tests \
+                 give the exact names other tools expect. Date-based tools accept start_date/end_date (YYYY-MM-DD) \
+                 or a days shorthand, and echo the effective range. get_recent_labs, \
+                 get_symptom_history and get_daily_summaries return pages of at most \
+                 max_items (default 100) with a total count; pass next_cursor back as \
+                 cursor for the next page, and omit_fields (e.g. notes) to shrink \
+                 responses. Records can \
+                 also be attached as resources: tracker://labs/session/{id}, \
+                 tracker://symptoms/day/{date}, tracker://tests/{name}/history \
+                 (percent-encode the name) and tracker://summary/current. Prompts \
+                 prepare an appointment summary, explain abnormal labs and \
+                 summarise recent symptoms. When the user has allowed it in the \
+                 app, log_symptoms, add_lab_session and add_daily_note record data; \
+                 each write can be reviewed and undone in the app. The user's access \
+                 settings may hide some tools and records, cap how far back data \
+                 goes or redact notes as \"[redacted]\".".into(),
+            ),
+        }
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult::with_all_items(allowed_tools(&self.tool_router)?))
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let client = client_name(&context);
+        let (name, arguments) = (request.name.clone(), request.arguments.clone());
+        let result = async {
+            check_tool_allowed(&request.name)?;
+            self.tool_router.call(ToolCallContext::new(self, request, context)).await
+        }
+        .await;
+        let row_count = result.as_ref().ok()
+            .and_then(|r| r.structured_content.as_ref())
+            .and_then(json_row_count);
+        log_access(&client, KIND_TOOL, &name, arguments.as_ref(), row_count, result.as_ref().err());
+        result
+    }
+
+    fn get_tool(&self, name: &str) -> Option<Tool> {
+        let policy = open_db().and_then(|conn| load_policy(&conn)).ok()?;
+        if !policy.allows_tool(name) {
+            return None;
+        }
+        self.tool_router.get(name).cloned()
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let conn = open_db()?;
+        check_enabled(&conn)?;
+        let policy = load_policy(&conn)?;
+        Ok(ListResourcesResult::with_all_items(list_tracker_resources(&conn, &policy)?))
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        let policy = open_db().and_then(|conn| load_policy(&conn))?;
+        let templates = vec![
+            resource_template(
+                "tracker://labs/session/{id}",
+                "Lab session",
+                "All results from one lab draw with flags and reference ranges",
+            ),
+            resource_template(
+                "tracker://symptoms/day/{date}",
+                "Symptom day",
+                "Wellness score, daily notes and symptom severities for one day (YYYY-MM-DD)",
+            ),
+            resource_template(
+                "tracker://tests/{name}/history",
+                "Test history",
+                "Every recorded result for one lab test, oldest first. Percent-encode the test name",
+            ),
+        ];
+        Ok(ListResourceTemplatesResult::with_all_items(
+            templates
+                .into_iter()
+                .filter(|t| {
+                    let path = t.uri_template.trim_start_matches("tracker://");
+                    resource_allowed(&policy, &path.split('/').collect::<Vec<_>>())
+                })
+                .collect(),
+        ))
+    }
+
+    async fn read_resource(
+        &self,
+        ReadResourceRequestParams { uri, .. }: ReadResourceRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let result = open_db().and_then(|conn| {
+            check_enabled(&conn)?;
+            let policy = load_policy(&conn)?;
+            read_tracker_resource(&conn, &policy, &uri)
+        });
+        let row_count = result.as_ref().ok()
+            .and_then(|r| r.contents.first())
+            .and_then(|contents| match contents {
+                ResourceContents::TextResourceContents { text, .. } => {
+                    serde_json::from_str(text).ok().as_ref().and_then(json_row_count)
+                }
+                _ => None,
+            });
+        log_access(&client_name(&context), KIND_RESOURCE, &uri, None, row_count, result.as_ref().err());
+        result
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        let policy = open_db().and_then(|conn| load_policy(&conn))?;
+        let prompts = tracker_prompts()
+            .into_iter()
+            .filter(|prompt| prompt_allowed(&policy, &prompt.name))
+            .collect();
+        Ok(ListPromptsResult::with_all_items(prompts))
+    }
+
+    async fn get_prompt(
+        &self,
+        GetPromptRequestParams { name, arguments, .. }: GetPromptRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        let arguments = arguments.unwrap_or_default();
+        let result = open_db().and_then(|conn| {
+            check_enabled(&conn)?;
+            build_prompt(&conn, &name, &arguments)
+        });
+        log_access(&client_name(&context), KIND_PROMPT, &name, Some(&arguments), None, result.as_ref().err());
+        result
+    }
+}
+
+const DEFAULT_HTTP_PORT: u16 = 8765;
+
+#[derive(Debug, PartialEq)]
+enum Transport {
+    Stdio,
+    Http(SocketAddr),
+}
+
+const USAGE: &str = "Usage: mcp-server [--http [--host ADDR] [--port PORT]]";
+
+/// Port from the app's Settings, so the URL shown there matches.
+fn configured_http_port() -> u16 {
+    open_db()
+        .ok()
+        .and_then(|conn| {
+            conn.query_row("SELECT value FROM settings WHERE key = 'mcp_http_port'", [], |r| r.get::<_, String>(0))
+                .ok()
+        })
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_HTTP_PORT)
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> anyhow::Result<Transport> {
+    let mut http = false;
+    let mut host = None;
+    let mut port = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--http" => http = true,
+            "--host" => {
+                let value = args.next().ok_or_else(|| anyhow::anyhow!("--host needs an address\n{}", USAGE))?;
+                host = Some(value.parse::<IpAddr>().map_err(|_| anyhow::anyhow!("Invalid --host address: {}", value))?);
+            }
+            "--port" => {
+                let value = args.next().ok_or_else(|| anyhow::anyhow!("--port needs a number\n{}", USAGE))?;
+                port = Some(value.parse::<u16>().map_err(|_| anyhow::anyhow!("Invalid --port: {}", value))?);
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            other => anyhow::bail!("Unknown argument: {}\n{}", other, USAGE),
+        }
+    }
+    if !http {
+        if host.is_some() || port.is_some() {
+            anyhow::bail!("--host and --port only apply with --http\n{}", USAGE);
+        }
+        return Ok(Transport::Stdio);
+    }
+    // Localhost only unless explicitly asked otherwise
+    let host = host.unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+    if !host.is_loopback() {
+        tracing::warn!(
+            "Listening on {}, which is reachable from other machines; anyone with the token can read your health data",
+            host
+        );
+    }
+    Ok(Transport::Http(SocketAddr::new(host, port.unwrap_or_else(configured_http_port))))
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+
+    let transport = parse_args(std::env::args().skip(1))?;
+    tracing::info!("Starting Symptom Test Tracker MCP server");
+
+    match transport {
+        Transport::Stdio => {
+            let service = TrackerMcp::new()
+                .serve(stdio())
+                .await
+                .inspect_err(|e| {
+                    tracing::error!("serving error: {:?}", e);
+                })?;
+            service.waiting().await?;
+        }
+        Transport::Http(addr) => http::serve(addr).await?,
+    }
+    Ok(())
+}
@@ -0,0 +1,642 @@
+// Calls each tool against a small fixture database and checks the structured
+// content against the tool's published output schema.
+
+use super::*;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tauri_app_lib::db::Database;
+
+thread_local! {
+    static FIXTURE_DB: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// The fixture database for the current test, if it has set one up.
+pub(super) fn fixture_db_path() -> Option<PathBuf> {
+    FIXTURE_DB.with(|path| path.borrow().clone())
+}
+
+/// Creates a fresh database in a temporary directory and points `db_path` at
+/// it for the rest of the calling test.
+pub(super) fn fixture() -> TrackerMcp {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "mcp-server-test-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    let path = dir.join("tracker.db");
+    let db = Database::open(&path).expect("create fixture database");
+    seed(&db.conn.lock().unwrap());
+    FIXTURE_DB.with(|fixture| *fixture.borrow_mut() = Some(path));
+    TrackerMcp::new()
+}
+
+fn seed(conn: &Connection) {
+    conn.execute_batch(
+        "UPDATE settings SET value = 'true' WHERE key IN ('mcp_enabled', 'mcp_write_enabled');
+         INSERT INTO vital_readings (measured_at, systolic_bp, diastolic_bp, heart_rate, notes)
+           VALUES ('2024-01-09 08:00', 150, 95, 72, 'after stairs');
+         INSERT INTO flares (start_date, end_date, severity, triggers)
+           VALUES ('2024-01-08', '2024-01-14', 3, 'cold weather');
+         INSERT INTO flare_systems (flare_id, category) VALUES (last_insert_rowid(), 'Musculoskeletal');
+         INSERT INTO warfarin_doses (week_start, weekly_dose_mg) VALUES ('2024-01-08', 35);
+         INSERT INTO custom_lab_tests (name, panel, unit, ref_low, ref_high, description)
+           VALUES ('Anti-dsDNA', 'Autoimmune', 'IU/mL', 0, 30, 'Antibodies to double-stranded DNA');",
+    )
+    .unwrap();
+
+    for (date, crp, inr) in [("2024-01-10", 12.0, 2.4), ("2024-02-10", 4.0, 3.4)] {
+        let session = serde_json::from_value(json!({
+            "id": null, "test_date": date, "lab_name": "Quest", "notes": "fasting draw",
+        }))
+        .unwrap();
+        let results: Vec<LabResult> = [
+            ("WBC", "CBC", 6.1, "K/uL", 4.0, 11.0),
+            ("CRP", "Inflammation", crp, "mg/L", 0.0, 5.0),
+            ("INR", "Coagulation", inr, "", 2.0, 3.0),
+        ]
+        .into_iter()
+        .map(|(test_name, panel, value, unit, low, high)| {
+            serde_json::from_value(json!({
+                "id": null, "session_id": null, "test_name": test_name, "panel": panel,
+                "value": value, "text_value": "", "unit": unit,
+                "ref_range_low": low, "ref_range_high": high,
+                "flag": labs::auto_flag(Some(value), Some(low), Some(high)),
+            }))
+            .unwrap()
+        })
+        .collect();
+        labs::save_session(conn, &session, &results, mcp_writes::SOURCE_APP).unwrap();
+    }
+
+    let symptom_id = |name: &str| -> i64 {
+        conn.query_row("SELECT id FROM symptoms WHERE name = ?1", [name], |r| r.get(0)).unwrap()
+    };
+    let (fatigue, joint_pain) = (symptom_id("Fatigue"), symptom_id("Joint Pain"));
+    for (date, severity, wellness) in [
+        ("2024-01-08", 6, 2),
+        ("2024-01-10", 7, 2),
+        ("2024-01-12", 4, 3),
+        ("2024-02-09", 2, 4),
+        ("2024-02-11", 1, 4),
+    ] {
+        let entries: Vec<SymptomEntry> = serde_json::from_value(json!([
+            { "symptom_id": fatigue, "severity": severity, "notes": "worse in the afternoon" },
+            { "symptom_id": joint_pain, "severity": severity / 2, "notes": "" },
+        ]))
+        .unwrap();
+        symptoms::save_day(conn, date, &entries, wellness, "stiff hands", mcp_writes::SOURCE_APP).unwrap();
+    }
+}
+
+fn params<T: serde::de::DeserializeOwned>(value: Value) -> Parameters<T> {
+    Parameters(serde_json::from_value(value).expect("valid tool arguments"))
+}
+
+fn today() -> String {
+    // SQLite's date('now'), which the look-back limit counts from, is UTC
+    chrono::Utc::now().date_naive().to_string()
+}
+
+fn range() -> Value {
+    json!({ "start_date": "2024-01-01", "end_date": "2024-03-01" })
+}
+
+/// Checks a successful result: a text summary first, and structured content
+/// with the fields the tool's output schema declares. Returns the content.
+fn check(mcp: &TrackerMcp, tool: &str, result: Result<CallToolResult, McpError>) -> Value {
+    let result = result.unwrap_or_else(|e| panic!("{} failed: {}", tool, e.message));
+    assert_ne!(result.is_error, Some(true), "{} returned an error", tool);
+    let summary = result.content.first().and_then(|c| c.as_text()).map(|t| t.text.clone());
+    assert!(
+        summary.as_deref().is_some_and(|s| !s.is_empty() && !s.starts_with('{')),
+        "{} has no text summary: {:?}",
+        tool,
+        summary
+    );
+
+    let content = result.structured_content.unwrap_or_else(|| panic!("{} has no structured content", tool));
+    let fields = content.as_object().unwrap_or_else(|| panic!("{} content is not an object", tool));
+    let schema = mcp
+        .tool_router
+        .get(tool)
+        .and_then(|t| t.output_schema.clone())
+        .unwrap_or_else(|| panic!("{} has no output schema", tool));
+    let properties = schema["properties"].as_object().expect("schema properties");
+    for field in fields.keys() {
+        assert!(properties.contains_key(field), "{}: `{}` is not in the output schema", tool, field);
+    }
+    for field in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+        let field = field.as_str().unwrap();
+        assert!(fields.contains_key(field), "{}: required `{}` is missing", tool, field);
+    }
+    content
+}
+
+fn results_len(content: &Value) -> usize {
+    content["results"].as_array().map_or(0, Vec::len)
+}
+
+/// Saves the access policy the way the app's Settings do.
+fn set_policy(policy: Value) {
+    let policy: McpPolicy = serde_json::from_value(policy).expect("valid policy");
+    policy.save(&Connection::open(db_path()).unwrap()).unwrap();
+}
+
+#[test]
+fn every_tool_publishes_an_output_schema() {
+    let mcp = TrackerMcp::new();
+    for tool in mcp.tool_router.list_all() {
+        let schema = tool.output_schema.as_ref().unwrap_or_else(|| panic!("{} has no output schema", tool.name));
+        assert_eq!(schema.get("type"), Some(&json!("object")), "{}", tool.name);
+        assert!(
+            mcp_policy::TOOLS.iter().any(|t| t.name == tool.name),
+            "{} is missing from the access policy catalogue",
+            tool.name
+        );
+    }
+}
+
+#[test]
+fn lab_tools() {
+    let mcp = fixture();
+
+    let labs = check(&mcp, "get_recent_labs", mcp.get_recent_labs(params(range())));
+    assert_eq!(results_len(&labs), 2);
+    assert_eq!(labs["results"][0]["results"].as_array().unwrap().len(), 3);
+
+    let abnormal = check(&mcp, "get_abnormal_labs", mcp.get_abnormal_labs());
+    let flagged: Vec<&str> = abnormal["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["test_name"].as_str().unwrap())
+        .collect();
+    assert_eq!(flagged, ["INR"]);
+
+    let names = check(&mcp, "list_test_names", mcp.list_test_names());
+    assert_eq!(results_len(&names), 3);
+
+    let custom = check(&mcp, "list_custom_tests", mcp.list_custom_tests());
+    assert_eq!(custom["results"][0]["name"], "Anti-dsDNA");
+
+    let trend = check(
+        &mcp,
+        "get_trends",
+        mcp.get_trends(params(json!({ "test_name": "CRP", "start_date": "2024-01-01", "end_date": "2024-03-01" }))),
+    );
+    assert_eq!(results_len(&trend), 2);
+    assert_eq!(trend["results"][0]["flag"], "HH");
+}
+
+#[test]
+fn symptom_tools() {
+    let mcp = fixture();
+
+    let symptoms = check(&mcp, "list_symptoms", mcp.list_symptoms());
+    assert!(results_len(&symptoms) > 0);
+
+    let history = check(&mcp, "get_symptom_history", mcp.get_symptom_history(params(range())));
+    assert_eq!(results_len(&history), 5);
+
+    let summaries = check(&mcp, "get_daily_summaries", mcp.get_daily_summaries(params(range())));
+    assert_eq!(results_len(&summaries), 5);
+
+    let rollup = check(
+        &mcp,
+        "get_rollups",
+        mcp.get_rollups(params(json!({ "period": "month", "start_date": "2024-01-01", "end_date": "2024-02-29" }))),
+    );
+    assert_eq!(rollup["buckets"].as_array().unwrap().len(), 2);
+
+    let correlation = check(
+        &mcp,
+        "get_lab_symptom_correlation",
+        mcp.get_lab_symptom_correlation(params(json!({
+            "test_name": "CRP", "symptom": "Fatigue", "start_date": "2024-01-01", "end_date": "2024-03-01",
+        }))),
+    );
+    assert_eq!(correlation["n"], 2);
+    for window_days in [-1, 31] {
+        let args = json!({ "test_name": "CRP", "window_days": window_days });
+        assert!(mcp.get_lab_symptom_correlation(params(args)).is_err());
+    }
+}
+
+#[test]
+fn vitals_medication_flare_and_note_tools() {
+    let mcp = fixture();
+
+    let vitals = check(&mcp, "get_vitals", mcp.get_vitals(params(range())));
+    assert_eq!(results_len(&vitals), 1);
+    assert!(!vitals["results"][0]["flags"].as_array().unwrap().is_empty());
+
+    let inr = check(&mcp, "get_inr_report", mcp.get_inr_report(params(range())));
+    assert_eq!(inr["points"].as_array().unwrap().len(), 2);
+    assert_eq!(inr["doses"].as_array().unwrap().len(), 1);
+
+    let flares = check(&mcp, "get_flares", mcp.get_flares(params(range())));
+    assert_eq!(results_len(&flares), 1);
+
+    let hits = check(&mcp, "search_notes", mcp.search_notes(params(json!({ "query": "afternoon" }))));
+    assert_eq!(results_len(&hits), 5);
+}
+
+#[test]
+fn write_tools() {
+    let mcp = fixture();
+
+    let logged = check(
+        &mcp,
+        "log_symptoms",
+        mcp.log_symptoms(params(json!({
+            "date": "2024-02-12", "symptoms": [{ "symptom": "fatigue", "severity": 3 }], "wellness": 4,
+        }))),
+    );
+    assert!(logged["write_id"].as_i64().is_some());
+
+    let session = check(
+        &mcp,
+        "add_lab_session",
+        mcp.add_lab_session(params(json!({
+            "test_date": "2024-02-20", "results": [{ "test_name": "CRP", "value": 8.0 }],
+        }))),
+    );
+    assert!(session["session_id"].as_i64().is_some());
+
+    let note = check(
+        &mcp,
+        "add_daily_note",
+        mcp.add_daily_note(params(json!({ "date": "2024-02-12", "note": "slept badly" }))),
+    );
+    assert!(note["write_id"].as_i64().is_some());
+
+    let trend = check(
+        &mcp,
+        "get_trends",
+        mcp.get_trends(params(json!({ "test_name": "CRP", "start_date": "2024-01-01", "end_date": "2024-03-01" }))),
+    );
+    assert_eq!(trend["results"][2]["flag"], "H");
+}
+
+#[test]
+fn writes_without_a_wellness_score_store_the_default_reads_report() {
+    let mcp = fixture();
+    let wellness = |date: &str| read_resource_json(&format!("tracker://symptoms/day/{}", date)).unwrap()["wellness_score"].clone();
+    check(
+        &mcp,
+        "log_symptoms",
+        mcp.log_symptoms(params(json!({ "date": "2024-02-15", "symptoms": [{ "symptom": "fatigue", "severity": 3 }] }))),
+    );
+    assert_eq!(wellness("2024-02-15"), symptoms::DEFAULT_WELLNESS);
+    check(&mcp, "add_daily_note", mcp.add_daily_note(params(json!({ "date": "2024-02-16", "note": "rested" }))));
+    assert_eq!(wellness("2024-02-16"), symptoms::DEFAULT_WELLNESS);
+    // Logged days keep their own score
+    check(&mcp, "add_daily_note", mcp.add_daily_note(params(json!({ "date": "2024-02-11", "note": "rested" }))));
+    assert_eq!(wellness("2024-02-11"), 4);
+}
+
+#[test]
+fn write_tools_follow_the_access_policy() {
+    let mcp = fixture();
+    set_policy(json!({ "hidden_panels": ["inflammation"], "max_days": 30 }));
+    let blocked = |result: Result<CallToolResult, McpError>| {
+        let error = result.expect_err("write should be blocked");
+        assert!(error.message.contains("MCP access settings"), "{}", error.message);
+    };
+
+    // CRP was last stored under the hidden Inflammation panel
+    blocked(mcp.add_lab_session(params(json!({ "test_date": today(), "results": [{ "test_name": "CRP", "value": 8.0 }] }))));
+    blocked(mcp.add_lab_session(params(json!({
+        "test_date": today(), "results": [{ "test_name": "ESR", "panel": "Inflammation", "value": 30.0 }],
+    }))));
+    check(
+        &mcp,
+        "add_lab_session",
+        mcp.add_lab_session(params(json!({ "test_date": today(), "results": [{ "test_name": "WBC", "value": 7.0 }] }))),
+    );
+
+    // The fixture's dates are all before the look-back limit
+    blocked(mcp.add_lab_session(params(json!({ "test_date": "2024-02-20", "results": [{ "test_name": "WBC", "value": 7.0 }] }))));
+    blocked(mcp.log_symptoms(params(json!({ "date": "2024-02-12", "symptoms": [{ "symptom": "fatigue", "severity": 3 }] }))));
+    blocked(mcp.add_daily_note(params(json!({ "date": "2024-02-12", "note": "slept badly" }))));
+    check(
+        &mcp,
+        "log_symptoms",
+        mcp.log_symptoms(params(json!({ "symptoms": [{ "symptom": "fatigue", "severity": 3 }] }))),
+    );
+}
+
+#[test]
+fn blocked_tools_are_neither_listed_nor_callable() {
+    let mcp = fixture();
+    set_policy(json!({ "blocked_tools": ["get_vitals"], "blocked_categories": ["flares"] }));
+    let listed: Vec<String> = allowed_tools(&mcp.tool_router).unwrap().into_iter().map(|t| t.name.to_string()).collect();
+    for tool in ["get_vitals", "get_flares"] {
+        assert!(!listed.iter().any(|name| name == tool), "{} is listed", tool);
+        assert!(mcp.get_tool(tool).is_none());
+        let error = check_tool_allowed(tool).unwrap_err();
+        assert!(error.message.contains("MCP access settings"), "{}", error.message);
+    }
+    assert!(listed.iter().any(|name| name == "get_trends"));
+    check_tool_allowed("get_trends").unwrap();
+}
+
+#[test]
+fn hidden_panels_and_symptom_categories_are_left_out() {
+    let mcp = fixture();
+    // CRP is in Inflammation and Fatigue in General
+    set_policy(json!({ "hidden_panels": ["inflammation"], "hidden_symptom_categories": ["General"] }));
+
+    let labs = check(&mcp, "get_recent_labs", mcp.get_recent_labs(params(range())));
+    let tests: Vec<&str> = labs["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|s| s["results"].as_array().unwrap())
+        .map(|r| r["test_name"].as_str().unwrap())
+        .collect();
+    assert_eq!(tests.len(), 4);
+    assert!(!tests.contains(&"CRP"));
+
+    let history = check(&mcp, "get_symptom_history", mcp.get_symptom_history(params(range())));
+    assert!(!history.to_string().contains("Fatigue"));
+    assert!(history.to_string().contains("Joint Pain"));
+
+    let hits = |query: &str| {
+        let content = check(&mcp, "search_notes", mcp.search_notes(params(json!({ "query": query }))));
+        results_len(&content)
+    };
+    assert_eq!(hits("afternoon"), 0);
+    assert_eq!(hits("stiff"), 5);
+}
+
+#[test]
+fn look_back_limit_clamps_the_echoed_range() {
+    let mcp = fixture();
+    set_policy(json!({ "max_days": 30 }));
+    let cutoff = (chrono::Utc::now().date_naive() - chrono::Duration::days(30)).to_string();
+
+    let history = check(&mcp, "get_symptom_history", mcp.get_symptom_history(params(json!({ "days": 3650 }))));
+    assert_eq!(history["start_date"], cutoff.as_str());
+    assert_eq!(history["end_date"], today().as_str());
+    assert_eq!(results_len(&history), 0);
+
+    // A range ending before the limit can't be clamped
+    assert!(mcp.get_symptom_history(params(range())).is_err());
+}
+
+#[test]
+fn notes_are_redacted_by_category() {
+    let mcp = fixture();
+    set_policy(json!({ "redact_note_categories": ["symptoms"] }));
+
+    let history = check(&mcp, "get_symptom_history", mcp.get_symptom_history(params(range())));
+    assert!(history.to_string().contains("[redacted]"));
+    assert!(!history.to_string().contains("afternoon"));
+    let summaries = check(&mcp, "get_daily_summaries", mcp.get_daily_summaries(params(range())));
+    assert!(!summaries.to_string().contains("stiff hands"));
+
+    let labs = check(&mcp, "get_recent_labs", mcp.get_recent_labs(params(range())));
+    assert_eq!(labs["results"][0]["notes"], "fasting draw");
+
+    let hits = |query: &str| {
+        let content = check(&mcp, "search_notes", mcp.search_notes(params(json!({ "query": query }))));
+        results_len(&content)
+    };
+    assert_eq!(hits("afternoon"), 0);
+    assert_eq!(hits("fasting"), 2);
+}
+
+#[test]
+fn percent_decoding_needs_two_hex_digits() {
+    assert_eq!(percent_decode("Vitamin%20B12%2FFolate").as_deref(), Some("Vitamin B12/Folate"));
+    assert_eq!(percent_decode("Caf%C3%A9").as_deref(), Some("Café"));
+    for bad in ["%+1", "%-1", "%zz", "%4", "%", "%FF"] {
+        assert_eq!(percent_decode(bad), None, "{}", bad);
+    }
+}
+
+fn resource_uris(conn: &Connection) -> Vec<String> {
+    let policy = load_policy(conn).unwrap();
+    list_tracker_resources(conn, &policy).unwrap().into_iter().map(|r| r.raw.uri).collect()
+}
+
+fn read_resource_json(uri: &str) -> Result<Value, McpError> {
+    let conn = open_db()?;
+    let policy = load_policy(&conn)?;
+    let result = read_tracker_resource(&conn, &policy, uri)?;
+    match &result.contents[0] {
+        ResourceContents::TextResourceContents { text, .. } => Ok(serde_json::from_str(text).unwrap()),
+        other => panic!("{} is not text: {:?}", uri, other),
+    }
+}
+
+#[test]
+fn resources_list_and_read_sessions_days_and_test_history() {
+    let _mcp = fixture();
+    let uris = resource_uris(&open_db().unwrap());
+    assert_eq!(uris.len(), 1 + 2 + 5);
+    assert_eq!(uris[0], "tracker://summary/current");
+    assert!(uris.contains(&"tracker://symptoms/day/2024-02-11".to_string()));
+
+    let session = read_resource_json("tracker://labs/session/1").unwrap();
+    assert_eq!(session["test_date"], "2024-01-10");
+    assert_eq!(session["results"].as_array().unwrap().len(), 3);
+    let day = read_resource_json("tracker://symptoms/day/2024-01-10").unwrap();
+    assert!(day.to_string().contains("stiff hands"));
+    // %57 is "W"
+    let history = read_resource_json("tracker://tests/%57BC/history").unwrap();
+    assert_eq!(history["test_name"], "WBC");
+    assert_eq!(history["points"].as_array().unwrap().len(), 2);
+    assert!(read_resource_json("tracker://summary/current").unwrap()["latest_lab_session"].is_object());
+
+    for missing in ["tracker://labs/session/99", "tracker://symptoms/day/2024-01-09", "tracker://tests/ESR/history", "file:///etc/passwd"] {
+        assert!(read_resource_json(missing).is_err(), "{}", missing);
+    }
+    assert!(read_resource_json("tracker://tests/W%+1C/history").is_err());
+}
+
+#[test]
+fn blocked_categories_hide_their_resources() {
+    let _mcp = fixture();
+    set_policy(json!({ "blocked_categories": ["labs"] }));
+    let uris = resource_uris(&open_db().unwrap());
+    assert!(uris.iter().all(|uri| !uri.starts_with("tracker://labs/")), "{:?}", uris);
+    assert!(uris.contains(&"tracker://summary/current".to_string()));
+    for blocked in ["tracker://labs/session/1", "tracker://tests/WBC/history"] {
+        let error = read_resource_json(blocked).unwrap_err();
+        assert!(error.message.contains("MCP access settings"), "{}: {}", blocked, error.message);
+    }
+    read_resource_json("tracker://symptoms/day/2024-01-10").unwrap();
+
+    set_policy(json!({ "blocked_categories": ["labs", "symptoms"] }));
+    assert!(resource_uris(&open_db().unwrap()).is_empty());
+    assert!(read_resource_json("tracker://summary/current").is_err());
+}
+
+/// Titles of a prompt's data messages, i.e. their first lines.
+fn prompt_sections(name: &str, args: Value) -> Result<Vec<String>, McpError> {
+    let conn = open_db()?;
+    let args = args.as_object().cloned().unwrap_or_default();
+    let prompt = build_prompt(&conn, name, &args)?;
+    let messages = serde_json::to_value(&prompt.messages).unwrap();
+    Ok(messages
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|m| m["content"]["text"].as_str())
+        .filter(|text| text.contains("```json"))
+        .map(|text| text.lines().next().unwrap().trim_end_matches(':').to_string())
+        .collect())
+}
+
+#[test]
+fn prompts_embed_the_requested_range() {
+    let _mcp = fixture();
+    let sections = prompt_sections(PROMPT_APPOINTMENT, range()).unwrap();
+    assert_eq!(
+        sections,
+        [
+            "My latest abnormal lab values, each with the previous result",
+            "Labs drawn from 2024-01-01 to 2024-03-01",
+            "Monthly symptom and wellness rollup",
+            "Flare episodes in this period",
+        ]
+    );
+    let conn = open_db().unwrap();
+    let args = json!({ "days": "20", "end_date": "2024-02-20" }).as_object().cloned().unwrap();
+    let prompt = build_prompt(&conn, PROMPT_SYMPTOM_SUMMARY, &args).unwrap();
+    assert_eq!(prompt.description.as_deref(), Some("Symptom summary for 2024-01-31 to 2024-02-20"));
+    let args = json!({ "days": "soon" }).as_object().cloned().unwrap();
+    assert!(build_prompt(&conn, PROMPT_SYMPTOM_SUMMARY, &args).is_err());
+    assert!(build_prompt(&conn, "write_a_poem", &JsonObject::new()).is_err());
+}
+
+#[test]
+fn prompts_leave_out_blocked_categories() {
+    let _mcp = fixture();
+    set_policy(json!({ "blocked_categories": ["flares", "symptoms"] }));
+    let sections = prompt_sections(PROMPT_APPOINTMENT, range()).unwrap();
+    assert_eq!(sections.len(), 2);
+    assert!(sections.iter().all(|s| !s.contains("rollup") && !s.contains("Flare")), "{:?}", sections);
+    let error = prompt_sections(PROMPT_SYMPTOM_SUMMARY, range()).unwrap_err();
+    assert!(error.message.contains("MCP access settings"), "{}", error.message);
+
+    set_policy(json!({ "blocked_categories": ["labs"] }));
+    assert!(prompt_sections(PROMPT_APPOINTMENT, range()).is_err());
+    assert!(prompt_sections(PROMPT_ABNORMAL_LABS, json!({})).is_err());
+    assert_eq!(prompt_sections(PROMPT_SYMPTOM_SUMMARY, range()).unwrap(), ["Weekly symptom and wellness rollup"]);
+}
+
+#[test]
+fn paged_tools_follow_cursors_and_omit_fields() {
+    let mcp = fixture();
+
+    let mut dates = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = check(
+            &mcp,
+            "get_symptom_history",
+            mcp.get_symptom_history(params(json!({
+                "start_date": "2024-01-01", "end_date": "2024-03-01", "max_items": 2, "cursor": cursor,
+                "omit_fields": ["notes", "daily_notes"],
+            }))),
+        );
+        assert_eq!(page["total"], 5);
+        for day in page["results"].as_array().unwrap() {
+            assert!(day.get("daily_notes").is_none());
+            assert!(day["symptoms"][0].get("notes").is_none());
+            dates.push(day["date"].as_str().unwrap().to_string());
+        }
+        match page["next_cursor"].as_str() {
+            Some(next) => cursor = Some(next.to_string()),
+            None => break,
+        }
+    }
+    assert_eq!(dates, ["2024-02-11", "2024-02-09", "2024-01-12", "2024-01-10", "2024-01-08"]);
+
+    let first = check(&mcp, "get_recent_labs", mcp.get_recent_labs(params(json!({ "days": 10000, "max_items": 1 }))));
+    assert_eq!((first["returned"].as_i64(), first["total"].as_i64()), (Some(1), Some(2)));
+    let second = check(
+        &mcp,
+        "get_recent_labs",
+        mcp.get_recent_labs(params(json!({ "cursor": first["next_cursor"] }))),
+    );
+    assert_eq!(second["results"][0]["test_date"], "2024-01-10");
+    assert!(second["next_cursor"].is_null());
+
+    let summaries = check(
+        &mcp,
+        "get_daily_summaries",
+        mcp.get_daily_summaries(params(json!({ "days": 10000, "omit_fields": ["notes"] }))),
+    );
+    assert_eq!(summaries["returned"], 5);
+    assert!(summaries["results"][0].get("notes").is_none());
+
+    for args in [json!({ "omit_fields": ["note"] }), json!({ "max_items": 0 }), json!({ "cursor": "zz" })] {
+        assert!(mcp.get_daily_summaries(params(args)).is_err());
+    }
+}
+
+#[test]
+fn reads_see_one_snapshot_while_the_app_writes() {
+    let _mcp = fixture();
+    let count = |conn: &Connection| -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM lab_sessions", [], |r| r.get(0)).unwrap()
+    };
+
+    let read = open_db().unwrap();
+    assert_eq!(count(&read), 2);
+    let app = Connection::open(db_path()).unwrap();
+    app.execute("INSERT INTO lab_sessions (test_date) VALUES ('2024-03-01')", []).unwrap();
+    assert_eq!(count(&read), 2);
+    drop(read);
+
+    assert_eq!(count(&open_db().unwrap()), 3);
+}
+
+#[test]
+fn missing_or_newer_databases_are_reported() {
+    let mcp = fixture();
+    check(&mcp, "get_abnormal_labs", mcp.get_abnormal_labs());
+    let app = Connection::open(db_path()).unwrap();
+    app.pragma_update(None, "user_version", db::SCHEMA_VERSION + 1).unwrap();
+    let error = mcp.get_abnormal_labs().unwrap_err();
+    assert!(error.message.contains("schema version"), "{}", error.message);
+
+    let missing = db_path().with_file_name("missing.db");
+    FIXTURE_DB.with(|fixture| *fixture.borrow_mut() = Some(missing));
+    let error = mcp.get_abnormal_labs().unwrap_err();
+    assert!(error.message.contains("Open the app once"), "{}", error.message);
+}
+
+#[test]
+fn arguments_pick_the_transport() {
+    let _mcp = fixture();
+    let parse = |args: &[&str]| parse_args(args.iter().map(|arg| arg.to_string()));
+    let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    assert_eq!(parse(&[]).unwrap(), Transport::Stdio);
+    assert_eq!(parse(&["--http"]).unwrap(), Transport::Http(SocketAddr::new(localhost, DEFAULT_HTTP_PORT)));
+    let app = Connection::open(db_path()).unwrap();
+    app.execute("INSERT OR REPLACE INTO settings (key, value) VALUES ('mcp_http_port', '9100')", []).unwrap();
+    assert_eq!(parse(&["--http"]).unwrap(), Transport::Http(SocketAddr::new(localhost, 9100)));
+    assert_eq!(
+        parse(&["--http", "--host", "0.0.0.0", "--port", "8000"]).unwrap(),
+        Transport::Http("0.0.0.0:8000".parse().unwrap())
+    );
+
+    for args in [
+        &["--port", "8000"][..],
+        &["--http", "--host"],
+        &["--http", "--host", "localhost"],
+        &["--http", "--port", "70000"],
+        &["--verbose"],
+    ] {
+        assert!(parse(args).is_err(), "{:?}", args);
+    }
+}
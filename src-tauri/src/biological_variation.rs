@@ -0,0 +1,67 @@
+// Within-subject biological variation (CVi) and analytical imprecision (CVa),
+// both as percentages, for the common analytes in the default panels. CVi
+// values are rounded from the EFLM and Westgard biological variation
+// databases; CVa is the desirable imprecision of half the CVi.
+
+pub const BIOLOGICAL_VARIATION: [(&str, f64, f64); 39] = [
+    // CBC
+    ("WBC", 11.4, 5.7),
+    ("RBC", 3.2, 1.6),
+    ("Hemoglobin", 2.8, 1.4),
+    ("Hematocrit", 2.8, 1.4),
+    ("MCV", 1.3, 0.7),
+    ("MCH", 1.6, 0.8),
+    ("MCHC", 1.7, 0.9),
+    ("RDW", 3.5, 1.8),
+    ("Platelets", 9.1, 4.6),
+    ("Neutrophils", 16.1, 8.1),
+    ("Lymphocytes", 10.4, 5.2),
+    // Lipids
+    ("Total Cholesterol", 5.3, 2.7),
+    ("LDL", 8.3, 4.2),
+    ("HDL", 5.7, 2.9),
+    ("Triglycerides", 19.9, 10.0),
+    // Inflammation
+    ("CRP", 42.2, 21.1),
+    ("Ferritin", 14.2, 7.1),
+    ("Fibrinogen", 10.7, 5.4),
+    // Lupus/APS markers
+    ("C3", 5.2, 2.6),
+    ("C4", 8.9, 4.5),
+    // Metabolic / BMP
+    ("Sodium", 0.6, 0.3),
+    ("Potassium", 4.6, 2.3),
+    ("Chloride", 1.2, 0.6),
+    ("CO2", 4.8, 2.4),
+    ("BUN", 12.3, 6.2),
+    ("Creatinine", 4.4, 2.2),
+    ("eGFR", 4.4, 2.2),
+    ("Glucose", 5.0, 2.5),
+    ("Calcium", 1.9, 1.0),
+    ("ALT", 9.3, 4.7),
+    ("AST", 9.5, 4.8),
+    ("Alkaline Phosphatase", 6.4, 3.2),
+    ("Total Bilirubin", 21.8, 10.9),
+    ("Albumin", 2.5, 1.3),
+    ("Uric Acid", 9.0, 4.5),
+    // Thyroid
+    ("TSH", 17.7, 8.9),
+    ("Free T4", 4.9, 2.5),
+    // Coagulation
+    ("PT", 4.0, 2.0),
+    ("aPTT", 2.7, 1.4),
+];
+
+/// (CVi, CVa) for a test, matched case-insensitively.
+pub fn lookup(test_name: &str) -> Option<(f64, f64)> {
+    BIOLOGICAL_VARIATION
+        .iter()
+        .find(|(name, _, _)| name.eq_ignore_ascii_case(test_name))
+        .map(|(_, cvi, cva)| (*cvi, *cva))
+}
+
+/// Reference change value (%) at 95% two-sided confidence:
+/// 1.96 × √2 × √(CVa² + CVi²).
+pub fn reference_change_value(cvi: f64, cva: f64) -> f64 {
+    1.96 * std::f64::consts::SQRT_2 * (cva.powi(2) + cvi.powi(2)).sqrt()
+}
@@ -0,0 +1,54 @@
+use crate::analysis::{self, CorrelationResult, CrossCorrelationResult, SymptomTarget, TestSummary};
+use crate::date_range;
+use crate::db::Database;
+use tauri::State;
+
+/// Correlates `test_name` with a symptom's severity, or with the wellness
+/// score when `symptom_id` is omitted.
+#[tauri::command]
+pub fn get_lab_symptom_correlation(
+    db: State<Database>,
+    test_name: String,
+    symptom_id: Option<i64>,
+    window_days: i64,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<CorrelationResult, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    let target = symptom_id.map_or(SymptomTarget::Wellness, SymptomTarget::Symptom);
+    analysis::lab_symptom_correlation(&conn, &test_name, target, window_days, &range)
+}
+
+/// Lagged cross-correlation between two symptoms; either side may be the
+/// wellness score by omitting its id.
+#[tauri::command]
+pub fn get_symptom_cross_correlation(
+    db: State<Database>,
+    symptom_a_id: Option<i64>,
+    symptom_b_id: Option<i64>,
+    max_lag: i64,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<CrossCorrelationResult, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    let a = symptom_a_id.map_or(SymptomTarget::Wellness, SymptomTarget::Symptom);
+    let b = symptom_b_id.map_or(SymptomTarget::Wellness, SymptomTarget::Symptom);
+    analysis::cross_correlation(&conn, a, b, max_lag, &range)
+}
+
+#[tauri::command]
+pub fn get_test_summary(
+    db: State<Database>,
+    test_name: String,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<TestSummary, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    analysis::test_summary(&conn, &test_name, &range)
+}
@@ -0,0 +1,88 @@
+use crate::anticoagulation::{self, InrReport, TherapeuticRange, WarfarinDose};
+use crate::date_range;
+use crate::db::Database;
+use rusqlite::params;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_inr_therapeutic_range(db: State<Database>) -> Result<TherapeuticRange, String> {
+    let conn = db.conn.lock().unwrap();
+    Ok(anticoagulation::therapeutic_range(&conn))
+}
+
+#[tauri::command]
+pub fn save_inr_therapeutic_range(db: State<Database>, low: f64, high: f64) -> Result<(), String> {
+    if !(low > 0.0 && low < high) {
+        return Err("INR target range must satisfy 0 < low < high".to_string());
+    }
+    let conn = db.conn.lock().unwrap();
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES ('inr_target_low', ?1), ('inr_target_high', ?2)",
+        params![low.to_string(), high.to_string()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_warfarin_doses(db: State<Database>) -> Result<Vec<WarfarinDose>, String> {
+    let conn = db.conn.lock().unwrap();
+    let mut stmt = conn
+        .prepare("SELECT id, week_start, weekly_dose_mg, notes FROM warfarin_doses ORDER BY week_start DESC")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(WarfarinDose {
+                id: Some(row.get(0)?),
+                week_start: row.get(1)?,
+                weekly_dose_mg: row.get(2)?,
+                notes: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn save_warfarin_dose(db: State<Database>, dose: WarfarinDose) -> Result<i64, String> {
+    date_range::parse_date(&dose.week_start)?;
+    if !(dose.weekly_dose_mg.is_finite() && dose.weekly_dose_mg > 0.0) {
+        return Err("Weekly warfarin dose must be greater than 0 mg".to_string());
+    }
+    let conn = db.conn.lock().unwrap();
+    if let Some(id) = dose.id {
+        conn.execute(
+            "UPDATE warfarin_doses SET week_start = ?1, weekly_dose_mg = ?2, notes = ?3 WHERE id = ?4",
+            params![dose.week_start, dose.weekly_dose_mg, dose.notes, id],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(id)
+    } else {
+        conn.execute(
+            "INSERT INTO warfarin_doses (week_start, weekly_dose_mg, notes) VALUES (?1, ?2, ?3)",
+            params![dose.week_start, dose.weekly_dose_mg, dose.notes],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(conn.last_insert_rowid())
+    }
+}
+
+#[tauri::command]
+pub fn delete_warfarin_dose(db: State<Database>, id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().unwrap();
+    conn.execute("DELETE FROM warfarin_doses WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_inr_report(
+    db: State<Database>,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<InrReport, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    anticoagulation::build_inr_report(&conn, &range.start_date, &range.end_date)
+}
@@ -0,0 +1,135 @@
+use crate::date_range::{self, DateRange};
+use crate::db::Database;
+use chrono::Duration;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tauri::State;
+
+/// One calendar day. Days with nothing logged are still returned, with
+/// `logged: false` and no scores, so tracking gaps show up in the heatmap.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CalendarDay {
+    pub date: String,
+    pub logged: bool,
+    pub wellness_score: Option<i64>,
+    pub max_severity: Option<i64>,
+    pub symptoms_present: i64,
+    pub labs_drawn: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CalendarHeatmap {
+    pub start_date: String,
+    pub end_date: String,
+    pub days: Vec<CalendarDay>,
+}
+
+fn query_calendar(conn: &Connection, range: &DateRange) -> Result<Vec<CalendarDay>, String> {
+    let mut stmt = conn
+        .prepare("SELECT log_date, wellness_score FROM daily_summaries WHERE log_date >= ?1 AND log_date <= ?2")
+        .map_err(|e| e.to_string())?;
+    let wellness: HashMap<String, i64> = stmt
+        .query_map(params![range.start_date, range.end_date], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT log_date, MAX(severity), SUM(severity > 0) FROM symptom_logs
+             WHERE log_date >= ?1 AND log_date <= ?2
+             GROUP BY log_date",
+        )
+        .map_err(|e| e.to_string())?;
+    let symptoms: HashMap<String, (i64, i64)> = stmt
+        .query_map(params![range.start_date, range.end_date], |row| {
+            Ok((row.get(0)?, (row.get(1)?, row.get(2)?)))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT test_date FROM lab_sessions WHERE test_date >= ?1 AND test_date <= ?2")
+        .map_err(|e| e.to_string())?;
+    let lab_dates: HashSet<String> = stmt
+        .query_map(params![range.start_date, range.end_date], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let end = date_range::parse_date(&range.end_date)?;
+    let mut day = date_range::parse_date(&range.start_date)?;
+    let mut days = Vec::new();
+    while day <= end {
+        let date = day.format("%Y-%m-%d").to_string();
+        let wellness_score = wellness.get(&date).copied();
+        let symptom_row = symptoms.get(&date).copied();
+        let logged = wellness_score.is_some() || symptom_row.is_some();
+        days.push(CalendarDay {
+            logged,
+            wellness_score,
+            max_severity: if logged { Some(symptom_row.map_or(0, |(max, _)| max)) } else { None },
+            symptoms_present: symptom_row.map_or(0, |(_, present)| present),
+            labs_drawn: lab_dates.contains(&date),
+            date,
+        });
+        day += Duration::days(1);
+    }
+    Ok(days)
+}
+
+#[tauri::command]
+pub fn get_calendar_heatmap(
+    db: State<Database>,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<CalendarHeatmap, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    let days = query_calendar(&conn, &range)?;
+    Ok(CalendarHeatmap {
+        start_date: range.start_date,
+        end_date: range.end_date,
+        days,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn every_day_is_returned_and_untracked_days_have_no_scores() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        conn.execute_batch(
+            "INSERT INTO daily_summaries (log_date, wellness_score) VALUES ('2024-02-28', 6), ('2024-03-01', 4);
+             INSERT INTO symptom_logs (log_date, symptom_id, severity) VALUES
+                ('2024-03-01', 1, 3), ('2024-03-01', 2, 7), ('2024-03-01', 3, 0);
+             INSERT INTO lab_sessions (test_date) VALUES ('2024-02-29');",
+        )
+        .unwrap();
+        let range = DateRange { start_date: "2024-02-28".to_string(), end_date: "2024-03-02".to_string() };
+        let days = query_calendar(&conn, &range).unwrap();
+        let dates: Vec<&str> = days.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(dates, ["2024-02-28", "2024-02-29", "2024-03-01", "2024-03-02"]);
+        let summary: Vec<_> = days
+            .iter()
+            .map(|d| (d.logged, d.wellness_score, d.max_severity, d.symptoms_present, d.labs_drawn))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                // Logged without symptoms: severity 0 rather than unknown
+                (true, Some(6), Some(0), 0, false),
+                (false, None, None, 0, true),
+                (true, Some(4), Some(7), 2, false),
+                (false, None, None, 0, false),
+            ]
+        );
+    }
+}
@@ -1,4 +1,10 @@
 use crate::db::Database;
+use crate::date_range::DateRange;
+use crate::flares::{self, FlareWithLabs};
+use crate::rollups::{self, Rollup, RollupPeriod};
+use crate::symptoms;
+use crate::tags;
+use crate::vitals;
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
 use tauri::State;
@@ -37,6 +43,22 @@ struct ExportSymptomEntry {
     category: String,
     severity: i64,
     notes: String,
+    body_locations: Vec<String>,
+    duration_minutes: Option<i64>,
+    onset_time: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportVitalReading {
+    measured_at: String,
+    systolic_bp: Option<f64>,
+    diastolic_bp: Option<f64>,
+    heart_rate: Option<f64>,
+    temperature_c: Option<f64>,
+    weight_kg: Option<f64>,
+    spo2: Option<f64>,
+    notes: String,
+    flags: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,18 +67,35 @@ struct ExportData {
     labs: Option<Vec<ExportLabSession>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     symptoms: Option<Vec<ExportSymptomDay>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vitals: Option<Vec<ExportVitalReading>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flares: Option<Vec<FlareWithLabs>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<Rollup>,
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn export_data(
     db: State<Database>,
     start_date: String,
     end_date: String,
     include_labs: bool,
     include_symptoms: bool,
+    include_vitals: bool,
+    include_flares: bool,
+    summary_period: Option<String>,
+    tag_id: Option<i64>,
     format: String,
 ) -> Result<String, String> {
+    let summary_period = summary_period.as_deref().map(RollupPeriod::parse).transpose()?;
     let conn = db.conn.lock().unwrap();
+    // With a tag filter, every section is limited to dates carrying the tag:
+    // labs, symptom days, vital readings, flares with a tagged day (and their
+    // labs on tagged days) and the summary's logged days
+    let tagged = tag_id.map(|id| tags::tagged_dates(&conn, id)).transpose()?;
+    let keep = |date: &str| tagged.as_ref().is_none_or(|t| t.contains(date));
 
     let labs = if include_labs {
         let mut stmt = conn
@@ -77,6 +116,9 @@ pub fn export_data(
 
         let mut export_sessions = Vec::new();
         for (sid, test_date, lab_name, notes) in sessions {
+            if !keep(&test_date) {
+                continue;
+            }
             let mut rstmt = conn
                 .prepare(
                     "SELECT test_name, panel, value, text_value, unit, ref_range_low, ref_range_high, flag
@@ -129,17 +171,22 @@ pub fn export_data(
 
         let mut export_days = Vec::new();
         for date in dates {
+            if !keep(&date) {
+                continue;
+            }
             let (wellness_score, daily_notes) = conn
                 .query_row(
                     "SELECT wellness_score, notes FROM daily_summaries WHERE log_date = ?1",
                     params![date],
                     |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
                 )
-                .unwrap_or((5, String::new()));
+                .unwrap_or((symptoms::DEFAULT_WELLNESS, String::new()));
 
             let mut sstmt = conn
                 .prepare(
-                    "SELECT s.name, s.category, sl.severity, sl.notes
+                    "SELECT s.name, s.category, sl.severity, sl.notes, sl.duration_minutes, sl.onset_time,
+                            (SELECT group_concat(l.location, ';') FROM symptom_log_locations l
+                             WHERE l.log_date = sl.log_date AND l.symptom_id = sl.symptom_id)
                      FROM symptom_logs sl
                      JOIN symptoms s ON sl.symptom_id = s.id
                      WHERE sl.log_date = ?1
@@ -153,6 +200,12 @@ pub fn export_data(
                         category: row.get(1)?,
                         severity: row.get(2)?,
                         notes: row.get(3)?,
+                        duration_minutes: row.get(4)?,
+                        onset_time: row.get(5)?,
+                        body_locations: row
+                            .get::<_, Option<String>>(6)?
+                            .map(|l| l.split(';').map(String::from).collect())
+                            .unwrap_or_default(),
                     })
                 })
                 .map_err(|e| e.to_string())?
@@ -171,7 +224,63 @@ pub fn export_data(
         None
     };
 
-    let data = ExportData { labs, symptoms };
+    let vitals = if include_vitals {
+        let readings = vitals::query_readings(&conn, &start_date, &end_date)?;
+        Some(
+            readings
+                .into_iter()
+                .filter(|r| keep(r.measured_at.get(..10).unwrap_or(&r.measured_at)))
+                .map(|r| ExportVitalReading {
+                    flags: r.flags.iter().map(|f| format!("{} {}", f.kind, f.flag)).collect(),
+                    measured_at: r.measured_at,
+                    systolic_bp: r.systolic_bp,
+                    diastolic_bp: r.diastolic_bp,
+                    heart_rate: r.heart_rate,
+                    temperature_c: r.temperature_c,
+                    weight_kg: r.weight_kg,
+                    spo2: r.spo2,
+                    notes: r.notes,
+                })
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    let flares = if include_flares {
+        let mut flares = flares::query_flares_with_labs(&conn, &start_date, &end_date)?;
+        if let Some(tagged) = &tagged {
+            flares.retain(|f| {
+                let end = f.flare.end_date.as_deref().unwrap_or("9999-12-31");
+                tagged.iter().any(|d| d.as_str() >= f.flare.start_date.as_str() && d.as_str() <= end)
+            });
+            for f in &mut flares {
+                f.labs.retain(|l| keep(&l.test_date));
+            }
+        }
+        Some(flares)
+    } else {
+        None
+    };
+
+    let summary = match summary_period {
+        Some(period) => {
+            let range = DateRange {
+                start_date: start_date.clone(),
+                end_date: end_date.clone(),
+            };
+            Some(rollups::query_rollups_on(&conn, period, &range, tagged.as_ref())?)
+        }
+        None => None,
+    };
+
+    let data = ExportData {
+        labs,
+        symptoms,
+        vitals,
+        flares,
+        summary,
+    };
 
     match format.as_str() {
         "csv" => export_csv(&data),
@@ -209,11 +318,11 @@ fn export_csv(data: &ExportData) -> Result<String, String> {
             csv.push('\n');
         }
         csv.push_str("Symptom Logs\n");
-        csv.push_str("Date,Wellness Score,Daily Notes,Symptom,Category,Severity,Notes\n");
+        csv.push_str("Date,Wellness Score,Daily Notes,Symptom,Category,Severity,Notes,Body Locations,Duration (min),Onset\n");
         for day in symptoms {
             for s in &day.symptoms {
                 csv.push_str(&format!(
-                    "{},{},{},{},{},{},{}\n",
+                    "{},{},{},{},{},{},{},{},{},{}\n",
                     escape_csv(&day.date),
                     day.wellness_score,
                     escape_csv(&day.daily_notes),
@@ -221,6 +330,96 @@ fn export_csv(data: &ExportData) -> Result<String, String> {
                     escape_csv(&s.category),
                     s.severity,
                     escape_csv(&s.notes),
+                    escape_csv(&s.body_locations.join("; ")),
+                    s.duration_minutes.map(|m| m.to_string()).unwrap_or_default(),
+                    escape_csv(s.onset_time.as_deref().unwrap_or_default()),
+                ));
+            }
+        }
+    }
+
+    if let Some(vitals) = &data.vitals {
+        if !csv.is_empty() {
+            csv.push('\n');
+        }
+        csv.push_str("Vital Signs\n");
+        csv.push_str("Measured At,Systolic BP,Diastolic BP,Heart Rate,Temperature (C),Weight (kg),SpO2,Flags,Notes\n");
+        let num = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+        for v in vitals {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                escape_csv(&v.measured_at),
+                num(v.systolic_bp),
+                num(v.diastolic_bp),
+                num(v.heart_rate),
+                num(v.temperature_c),
+                num(v.weight_kg),
+                num(v.spo2),
+                escape_csv(&v.flags.join("; ")),
+                escape_csv(&v.notes),
+            ));
+        }
+    }
+
+    if let Some(flares) = &data.flares {
+        if !csv.is_empty() {
+            csv.push('\n');
+        }
+        csv.push_str("Flares\n");
+        csv.push_str("Start Date,End Date,Severity,Affected Systems,Triggers,Treatment Notes,Lab Dates,Abnormal Results\n");
+        for f in flares {
+            let lab_dates: Vec<&str> = f.labs.iter().map(|l| l.test_date.as_str()).collect();
+            let abnormal: Vec<String> = f
+                .labs
+                .iter()
+                .flat_map(|l| {
+                    l.results
+                        .iter()
+                        .filter(|r| !r.flag.is_empty() && r.flag != "N")
+                        .map(move |r| format!("{} {} ({})", l.test_date, r.test_name, r.flag))
+                })
+                .collect();
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{}\n",
+                escape_csv(&f.flare.start_date),
+                escape_csv(f.flare.end_date.as_deref().unwrap_or_default()),
+                f.flare.severity,
+                escape_csv(&f.flare.affected_systems.join("; ")),
+                escape_csv(&f.flare.triggers),
+                escape_csv(&f.flare.treatment_notes),
+                escape_csv(&lab_dates.join("; ")),
+                escape_csv(&abnormal.join("; ")),
+            ));
+        }
+    }
+
+    if let Some(summary) = &data.summary {
+        if !csv.is_empty() {
+            csv.push('\n');
+        }
+        csv.push_str(&format!("Summary ({})\n", if summary.period == "week" { "weekly" } else { "monthly" }));
+        csv.push_str("Period,Start Date,End Date,Days Logged,Mean Wellness,Symptom,Days Present,Frequency,Mean Severity,Max Severity\n");
+        for b in &summary.buckets {
+            let prefix = format!(
+                "{},{},{},{},{}",
+                escape_csv(&b.label),
+                escape_csv(&b.start_date),
+                escape_csv(&b.end_date),
+                b.days_logged,
+                b.mean_wellness.map(|w| format!("{:.2}", w)).unwrap_or_default(),
+            );
+            if b.symptoms.is_empty() {
+                csv.push_str(&format!("{},,,,,\n", prefix));
+            }
+            for s in &b.symptoms {
+                csv.push_str(&format!(
+                    "{},{},{},{:.2},{:.2},{}\n",
+                    prefix,
+                    escape_csv(&s.name),
+                    s.days_present,
+                    s.frequency,
+                    s.mean_severity,
+                    s.max_severity,
                 ));
             }
         }
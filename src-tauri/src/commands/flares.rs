@@ -0,0 +1,39 @@
+use crate::date_range;
+use crate::db::Database;
+use crate::flares::{self, Flare, FlareSuggestions, FlareWithLabs};
+use rusqlite::params;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_flares(db: State<Database>) -> Result<Vec<FlareWithLabs>, String> {
+    let conn = db.conn.lock().unwrap();
+    flares::query_flares_with_labs(&conn, "0000-01-01", "9999-12-31")
+}
+
+#[tauri::command]
+pub fn save_flare(db: State<Database>, flare: Flare) -> Result<i64, String> {
+    let conn = db.conn.lock().unwrap();
+    flares::save_flare(&conn, &flare)
+}
+
+#[tauri::command]
+pub fn delete_flare(db: State<Database>, id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().unwrap();
+    conn.execute("DELETE FROM flares WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn suggest_flares(
+    db: State<Database>,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    min_days: Option<i64>,
+    max_gap_days: Option<i64>,
+) -> Result<FlareSuggestions, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    flares::suggest_flares(&conn, &range, min_days.unwrap_or(3), max_gap_days.unwrap_or(2))
+}
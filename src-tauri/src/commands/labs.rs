@@ -1,30 +1,13 @@
+use crate::commands::medications::{self, MedicationEvent};
+use crate::date_range::{self, DateRange};
 use crate::db::Database;
-use rusqlite::params;
+use crate::labs::{self, LabResult, LabSession};
+use crate::mcp_writes;
+use crate::tags;
+use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct LabSession {
-    pub id: Option<i64>,
-    pub test_date: String,
-    pub lab_name: String,
-    pub notes: String,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct LabResult {
-    pub id: Option<i64>,
-    pub session_id: Option<i64>,
-    pub test_name: String,
-    pub panel: String,
-    pub value: Option<f64>,
-    pub text_value: String,
-    pub unit: String,
-    pub ref_range_low: Option<f64>,
-    pub ref_range_high: Option<f64>,
-    pub flag: String,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LabSessionWithResults {
     pub session: LabSession,
@@ -41,6 +24,21 @@ pub struct TrendPoint {
     pub ref_range_high: Option<f64>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabTrend {
+    pub start_date: String,
+    pub end_date: String,
+    pub points: Vec<TrendPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrendOverlay {
+    pub start_date: String,
+    pub end_date: String,
+    pub points: Vec<TrendPoint>,
+    pub medication_events: Vec<MedicationEvent>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CustomLabTest {
     pub id: Option<i64>,
@@ -176,48 +174,10 @@ pub fn save_lab_session(
     results: Vec<LabResult>,
 ) -> Result<i64, String> {
     let conn = db.conn.lock().unwrap();
-
-    let session_id = if let Some(id) = session.id {
-        conn.execute(
-            "UPDATE lab_sessions SET test_date = ?1, lab_name = ?2, notes = ?3 WHERE id = ?4",
-            params![session.test_date, session.lab_name, session.notes, id],
-        )
-        .map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM lab_results WHERE session_id = ?1", params![id])
-            .map_err(|e| e.to_string())?;
-        id
-    } else {
-        conn.execute(
-            "INSERT INTO lab_sessions (test_date, lab_name, notes) VALUES (?1, ?2, ?3)",
-            params![session.test_date, session.lab_name, session.notes],
-        )
-        .map_err(|e| e.to_string())?;
-        conn.last_insert_rowid()
-    };
-
-    for r in &results {
-        if r.value.is_none() && r.text_value.is_empty() {
-            continue;
-        }
-        conn.execute(
-            "INSERT INTO lab_results (session_id, test_name, panel, value, text_value, unit, ref_range_low, ref_range_high, flag)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-            params![
-                session_id,
-                r.test_name,
-                r.panel,
-                r.value,
-                r.text_value,
-                r.unit,
-                r.ref_range_low,
-                r.ref_range_high,
-                r.flag,
-            ],
-        )
-        .map_err(|e| e.to_string())?;
-    }
-
-    Ok(session_id)
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let id = labs::save_session(&tx, &session, &results, mcp_writes::SOURCE_APP)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(id)
 }
 
 #[tauri::command]
@@ -326,25 +286,19 @@ pub fn get_latest_abnormal(db: State<Database>) -> Result<Vec<LabResult>, String
     rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
 
-#[tauri::command]
-pub fn get_trends(
-    db: State<Database>,
-    test_name: String,
-    days: i64,
-) -> Result<Vec<TrendPoint>, String> {
-    let conn = db.conn.lock().unwrap();
+fn query_trends(conn: &Connection, test_name: &str, range: &DateRange) -> Result<Vec<TrendPoint>, String> {
     let mut stmt = conn
         .prepare(
             "SELECT s.test_date, r.value, r.text_value, r.flag, r.ref_range_low, r.ref_range_high
              FROM lab_results r
              JOIN lab_sessions s ON r.session_id = s.id
              WHERE r.test_name = ?1
-               AND s.test_date >= date('now', '-' || ?2 || ' days')
+               AND s.test_date >= ?2 AND s.test_date <= ?3
              ORDER BY s.test_date ASC",
         )
         .map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map(params![test_name, days], |row| {
+        .query_map(params![test_name, range.start_date, range.end_date], |row| {
             Ok(TrendPoint {
                 test_date: row.get(0)?,
                 value: row.get(1)?,
@@ -358,6 +312,49 @@ pub fn get_trends(
     rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn get_trends(
+    db: State<Database>,
+    test_name: String,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    tag_id: Option<i64>,
+) -> Result<LabTrend, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    let mut points = query_trends(&conn, &test_name, &range)?;
+    if let Some(tag_id) = tag_id {
+        let tagged = tags::tagged_dates(&conn, tag_id)?;
+        points.retain(|p| tagged.contains(&p.test_date));
+    }
+    Ok(LabTrend {
+        start_date: range.start_date,
+        end_date: range.end_date,
+        points,
+    })
+}
+
+#[tauri::command]
+pub fn get_trends_with_medications(
+    db: State<Database>,
+    test_name: String,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<TrendOverlay, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    let points = query_trends(&conn, &test_name, &range)?;
+    let medication_events = medications::events_in_window(&conn, &range)?;
+    Ok(TrendOverlay {
+        start_date: range.start_date,
+        end_date: range.end_date,
+        points,
+        medication_events,
+    })
+}
+
 #[tauri::command]
 pub fn get_all_test_names(db: State<Database>) -> Result<Vec<String>, String> {
     let conn = db.conn.lock().unwrap();
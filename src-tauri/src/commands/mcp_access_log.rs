@@ -0,0 +1,19 @@
+use crate::db::{self, Database};
+use crate::mcp_access_log::{self, McpAccess};
+use tauri::State;
+
+/// Prunes by the retention setting first, so changing it takes effect the
+/// next time the log is shown.
+#[tauri::command]
+pub fn get_mcp_access_log(db: State<Database>, limit: Option<i64>) -> Result<Vec<McpAccess>, String> {
+    let retention_days = mcp_access_log::retention_days(&db.conn.lock().unwrap());
+    let log = mcp_access_log::open(&mcp_access_log::path_for(&db::db_path()))?;
+    mcp_access_log::prune(&log, retention_days)?;
+    mcp_access_log::list(&log, limit.unwrap_or(200))
+}
+
+#[tauri::command]
+pub fn clear_mcp_access_log() -> Result<(), String> {
+    let log = mcp_access_log::open(&mcp_access_log::path_for(&db::db_path()))?;
+    mcp_access_log::clear(&log)
+}
@@ -0,0 +1,49 @@
+use crate::db::Database;
+use crate::mcp_policy::{self, McpPolicy, McpToolInfo};
+use serde::Serialize;
+use tauri::State;
+
+/// What the Settings policy editor can choose from.
+#[derive(Debug, Serialize)]
+pub struct McpPolicyOptions {
+    pub tools: &'static [McpToolInfo],
+    pub categories: &'static [&'static str],
+    pub note_categories: &'static [&'static str],
+    pub panels: Vec<String>,
+    pub symptom_categories: Vec<String>,
+}
+
+#[tauri::command]
+pub fn get_mcp_policy(db: State<Database>) -> Result<McpPolicy, String> {
+    let conn = db.conn.lock().unwrap();
+    McpPolicy::load(&conn)
+}
+
+#[tauri::command]
+pub fn save_mcp_policy(db: State<Database>, policy: McpPolicy) -> Result<(), String> {
+    let conn = db.conn.lock().unwrap();
+    policy.save(&conn)
+}
+
+#[tauri::command]
+pub fn get_mcp_policy_options(db: State<Database>) -> Result<McpPolicyOptions, String> {
+    let conn = db.conn.lock().unwrap();
+    let distinct = |sql: &str| -> Result<Vec<String>, String> {
+        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([], |row| row.get(0)).map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    };
+    Ok(McpPolicyOptions {
+        tools: mcp_policy::TOOLS,
+        categories: mcp_policy::CATEGORIES,
+        note_categories: mcp_policy::NOTE_CATEGORIES,
+        panels: distinct(
+            "SELECT panel FROM lab_results WHERE panel != ''
+             UNION SELECT panel FROM custom_lab_tests WHERE panel != ''
+             ORDER BY 1",
+        )?,
+        symptom_categories: distinct(
+            "SELECT DISTINCT category FROM symptoms WHERE category != '' ORDER BY 1",
+        )?,
+    })
+}
@@ -0,0 +1,17 @@
+use crate::db::Database;
+use crate::mcp_writes::{self, McpWrite};
+use tauri::State;
+
+#[tauri::command]
+pub fn get_mcp_writes(db: State<Database>, limit: Option<i64>) -> Result<Vec<McpWrite>, String> {
+    let conn = db.conn.lock().unwrap();
+    mcp_writes::list(&conn, limit.unwrap_or(50))
+}
+
+#[tauri::command]
+pub fn undo_mcp_write(db: State<Database>, id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().unwrap();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    mcp_writes::undo(&tx, id)?;
+    tx.commit().map_err(|e| e.to_string())
+}
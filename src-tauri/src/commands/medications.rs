@@ -0,0 +1,264 @@
+use crate::date_range::{self, DateRange};
+use crate::db::Database;
+use crate::stats;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+pub const EVENT_TYPES: [&str; 3] = ["start", "stop", "dose_change"];
+/// Longest before/after window for `get_medication_effect`.
+pub const MAX_WINDOW_DAYS: i64 = 3650;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MedicationEvent {
+    pub id: Option<i64>,
+    pub medication: String,
+    pub event_date: String,
+    pub event_type: String,
+    pub dose: String,
+    pub notes: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WindowSummary {
+    pub start_date: String,
+    pub end_date: String,
+    pub count: i64,
+    pub mean: Option<f64>,
+    pub median: Option<f64>,
+    pub abnormal_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MedicationEffect {
+    pub event: MedicationEvent,
+    pub test_name: String,
+    pub before: WindowSummary,
+    pub after: WindowSummary,
+}
+
+fn row_to_event(row: &rusqlite::Row) -> rusqlite::Result<MedicationEvent> {
+    Ok(MedicationEvent {
+        id: Some(row.get(0)?),
+        medication: row.get(1)?,
+        event_date: row.get(2)?,
+        event_type: row.get(3)?,
+        dose: row.get(4)?,
+        notes: row.get(5)?,
+    })
+}
+
+/// Medication events within `range`, oldest first.
+pub fn events_in_window(conn: &Connection, range: &DateRange) -> Result<Vec<MedicationEvent>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, medication, event_date, event_type, dose, notes FROM medication_events
+             WHERE event_date >= ?1 AND event_date <= ?2
+             ORDER BY event_date ASC, id ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![range.start_date, range.end_date], row_to_event)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_medication_events(db: State<Database>) -> Result<Vec<MedicationEvent>, String> {
+    let conn = db.conn.lock().unwrap();
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, medication, event_date, event_type, dose, notes FROM medication_events
+             ORDER BY event_date DESC, id DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_event).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn save_medication_event(db: State<Database>, event: MedicationEvent) -> Result<i64, String> {
+    if !EVENT_TYPES.contains(&event.event_type.as_str()) {
+        return Err(format!("Unknown medication event type: {}", event.event_type));
+    }
+    date_range::parse_date(&event.event_date)?;
+    let conn = db.conn.lock().unwrap();
+    if let Some(id) = event.id {
+        conn.execute(
+            "UPDATE medication_events SET medication = ?1, event_date = ?2, event_type = ?3, dose = ?4, notes = ?5 WHERE id = ?6",
+            params![event.medication, event.event_date, event.event_type, event.dose, event.notes, id],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(id)
+    } else {
+        conn.execute(
+            "INSERT INTO medication_events (medication, event_date, event_type, dose, notes) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![event.medication, event.event_date, event.event_type, event.dose, event.notes],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(conn.last_insert_rowid())
+    }
+}
+
+#[tauri::command]
+pub fn delete_medication_event(db: State<Database>, id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().unwrap();
+    conn.execute("DELETE FROM medication_events WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn summarize_window(
+    conn: &Connection,
+    test_name: &str,
+    start_date: String,
+    end_date: String,
+    include_start: bool,
+) -> Result<WindowSummary, String> {
+    let sql = if include_start {
+        "SELECT r.value, r.flag FROM lab_results r
+         JOIN lab_sessions s ON r.session_id = s.id
+         WHERE r.test_name = ?1 AND s.test_date >= ?2 AND s.test_date <= ?3"
+    } else {
+        "SELECT r.value, r.flag FROM lab_results r
+         JOIN lab_sessions s ON r.session_id = s.id
+         WHERE r.test_name = ?1 AND s.test_date > ?2 AND s.test_date <= ?3"
+    };
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows: Vec<(Option<f64>, String)> = stmt
+        .query_map(params![test_name, start_date, end_date], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let values: Vec<f64> = rows.iter().filter_map(|(v, _)| *v).collect();
+    let abnormal_count = rows
+        .iter()
+        .filter(|(_, flag)| !flag.is_empty() && flag != "N")
+        .count() as i64;
+
+    Ok(WindowSummary {
+        start_date,
+        end_date,
+        count: rows.len() as i64,
+        mean: stats::mean(&values),
+        median: stats::median(&values),
+        abnormal_count,
+    })
+}
+
+/// Summarises `test_name` in the `window_days` before and after a medication
+/// event. A draw on the event date itself counts as "before", since results
+/// are usually what prompted the change.
+pub fn medication_effect(
+    conn: &Connection,
+    event_id: i64,
+    test_name: String,
+    window_days: i64,
+) -> Result<MedicationEffect, String> {
+    if !(0..=MAX_WINDOW_DAYS).contains(&window_days) {
+        return Err(format!("window_days must be between 0 and {}", MAX_WINDOW_DAYS));
+    }
+    let event = conn
+        .query_row(
+            "SELECT id, medication, event_date, event_type, dose, notes FROM medication_events WHERE id = ?1",
+            params![event_id],
+            row_to_event,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let (window_start, window_end): (String, String) = conn
+        .query_row(
+            "SELECT date(?1, '-' || ?2 || ' days'), date(?1, '+' || ?2 || ' days')",
+            params![event.event_date, window_days],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let before = summarize_window(conn, &test_name, window_start, event.event_date.clone(), true)?;
+    let after = summarize_window(conn, &test_name, event.event_date.clone(), window_end, false)?;
+
+    Ok(MedicationEffect {
+        event,
+        test_name,
+        before,
+        after,
+    })
+}
+
+#[tauri::command]
+pub fn get_medication_effect(
+    db: State<Database>,
+    event_id: i64,
+    test_name: String,
+    window_days: i64,
+) -> Result<MedicationEffect, String> {
+    let conn = db.conn.lock().unwrap();
+    medication_effect(&conn, event_id, test_name, window_days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn add_event(conn: &Connection, event_date: &str) -> i64 {
+        conn.execute(
+            "INSERT INTO medication_events (medication, event_date, event_type) VALUES ('Prednisone', ?1, 'start')",
+            [event_date],
+        )
+        .unwrap();
+        conn.last_insert_rowid()
+    }
+
+    fn add_result(conn: &Connection, test_date: &str, value: f64, flag: &str) {
+        conn.execute("INSERT INTO lab_sessions (test_date) VALUES (?1)", [test_date]).unwrap();
+        conn.execute(
+            "INSERT INTO lab_results (session_id, test_name, value, flag) VALUES (?1, 'CRP', ?2, ?3)",
+            params![conn.last_insert_rowid(), value, flag],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn events_in_window_includes_both_ends() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        for date in ["2024-01-01", "2024-01-15", "2024-01-31", "2024-02-01"] {
+            add_event(&conn, date);
+        }
+        let range = DateRange { start_date: "2024-01-01".to_string(), end_date: "2024-01-31".to_string() };
+        let dates: Vec<String> = events_in_window(&conn, &range).unwrap().into_iter().map(|e| e.event_date).collect();
+        assert_eq!(dates, ["2024-01-01", "2024-01-15", "2024-01-31"]);
+    }
+
+    #[test]
+    fn effect_counts_the_event_day_as_before() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        let id = add_event(&conn, "2024-03-10");
+        add_result(&conn, "2024-02-01", 50.0, "H"); // outside the window
+        add_result(&conn, "2024-03-01", 20.0, "H");
+        add_result(&conn, "2024-03-10", 30.0, "H");
+        add_result(&conn, "2024-03-20", 4.0, "N");
+        add_result(&conn, "2024-03-24", 2.0, "N");
+
+        let effect = medication_effect(&conn, id, "CRP".to_string(), 14).unwrap();
+        assert_eq!((effect.before.start_date.as_str(), effect.before.end_date.as_str()), ("2024-02-25", "2024-03-10"));
+        assert_eq!((effect.before.count, effect.before.mean, effect.before.abnormal_count), (2, Some(25.0), 2));
+        assert_eq!((effect.after.start_date.as_str(), effect.after.end_date.as_str()), ("2024-03-10", "2024-03-24"));
+        assert_eq!((effect.after.count, effect.after.median, effect.after.abnormal_count), (2, Some(3.0), 0));
+    }
+
+    #[test]
+    fn effect_window_is_bounded() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        let id = add_event(&conn, "2024-03-10");
+        assert!(medication_effect(&conn, id, "CRP".to_string(), -1).is_err());
+        assert!(medication_effect(&conn, id, "CRP".to_string(), MAX_WINDOW_DAYS + 1).is_err());
+        assert!(medication_effect(&conn, id + 1, "CRP".to_string(), 14).is_err());
+    }
+}
@@ -2,3 +2,15 @@ pub mod labs;
 pub mod symptoms;
 pub mod export;
 pub mod settings;
+pub mod medications;
+pub mod anticoagulation;
+pub mod vitals;
+pub mod flares;
+pub mod analysis;
+pub mod calendar;
+pub mod rollups;
+pub mod search;
+pub mod tags;
+pub mod mcp_writes;
+pub mod mcp_policy;
+pub mod mcp_access_log;
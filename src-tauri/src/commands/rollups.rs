@@ -0,0 +1,20 @@
+use crate::date_range;
+use crate::db::Database;
+use crate::rollups::{self, Rollup, RollupPeriod};
+use tauri::State;
+
+/// Weekly (ISO week) or monthly rollups of symptom frequency, severity and
+/// wellness. `period` is "week" or "month".
+#[tauri::command]
+pub fn get_rollups(
+    db: State<Database>,
+    period: String,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<Rollup, String> {
+    let period = RollupPeriod::parse(&period)?;
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    rollups::query_rollups(&conn, period, &range)
+}
@@ -0,0 +1,9 @@
+use crate::db::Database;
+use crate::search::{self, NoteHit};
+use tauri::State;
+
+#[tauri::command]
+pub fn search_notes(db: State<Database>, query: String, limit: Option<i64>) -> Result<Vec<NoteHit>, String> {
+    let conn = db.conn.lock().unwrap();
+    search::search_notes(&conn, &query, limit)
+}
@@ -24,3 +24,21 @@ pub fn set_setting(key: String, value: String, db: State<'_, Database>) -> Resul
     .map_err(|e| e.to_string())?;
     Ok(())
 }
+
+/// Replaces the bearer token for the MCP server's HTTP transport. Clients using
+/// the old token are rejected from their next request.
+#[tauri::command]
+pub fn regenerate_mcp_http_token(db: State<'_, Database>) -> Result<String, String> {
+    let conn = db.conn.lock().unwrap();
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES ('mcp_http_token', lower(hex(randomblob(32))))",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = 'mcp_http_token'",
+        [],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
@@ -1,6 +1,15 @@
+use crate::date_range::{self, DateRange};
 use crate::db::Database;
-use rusqlite::params;
+use crate::mcp_writes;
+use crate::stats;
+use crate::symptoms::{
+    self, load_intraday, load_locations, time_sort_key, IntradayEntry, SymptomEntry, BODY_LOCATIONS,
+};
+use crate::tags;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use tauri::State;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -13,13 +22,6 @@ pub struct Symptom {
     pub sort_order: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SymptomEntry {
-    pub symptom_id: i64,
-    pub severity: i64,
-    pub notes: String,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DayLog {
     pub date: String,
@@ -35,6 +37,54 @@ pub struct SymptomLogEntry {
     pub category: String,
     pub severity: i64,
     pub notes: String,
+    pub intraday: Vec<IntradayEntry>,
+    pub body_locations: Vec<String>,
+    pub duration_minutes: Option<i64>,
+    pub onset_time: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BodyLocation {
+    pub key: String,
+    pub label: String,
+    pub is_joint: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LocationDay {
+    pub date: String,
+    pub locations: Vec<String>,
+    pub location_count: i64,
+    pub joint_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LocationFrequency {
+    pub location: String,
+    pub days: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BodyLocationSummary {
+    pub start_date: String,
+    pub end_date: String,
+    pub days: Vec<LocationDay>,
+    pub frequencies: Vec<LocationFrequency>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IntradayPoint {
+    pub date: String,
+    pub time_of_day: String,
+    pub severity: i64,
+    pub notes: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IntradayTrend {
+    pub start_date: String,
+    pub end_date: String,
+    pub points: Vec<IntradayPoint>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -43,10 +93,47 @@ pub struct WellnessTrendPoint {
     pub wellness_score: i64,
 }
 
+/// One logged day for a symptom. A day that was logged without an entry for
+/// the symptom has severity 0. Rolling averages cover the logged days in the
+/// trailing 7/30 calendar days, including this one.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SymptomTrendPoint {
     pub date: String,
+    pub severity: i64,
     pub present: bool,
+    pub has_notes: bool,
+    pub rolling_7: f64,
+    pub rolling_30: f64,
+    pub days_since_last: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WellnessTrend {
+    pub start_date: String,
+    pub end_date: String,
+    pub points: Vec<WellnessTrendPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SymptomTrend {
+    pub start_date: String,
+    pub end_date: String,
+    pub points: Vec<SymptomTrendPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SymptomTrendSeries {
+    pub symptom_id: i64,
+    pub name: String,
+    pub category: String,
+    pub points: Vec<SymptomTrendPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AllSymptomTrends {
+    pub start_date: String,
+    pub end_date: String,
+    pub symptoms: Vec<SymptomTrendSeries>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -56,50 +143,178 @@ pub struct SymptomNameEntry {
 }
 
 #[tauri::command]
-pub fn get_wellness_trends(db: State<Database>, days: i64) -> Result<Vec<WellnessTrendPoint>, String> {
+pub fn get_wellness_trends(
+    db: State<Database>,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    tag_id: Option<i64>,
+) -> Result<WellnessTrend, String> {
     let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
     let mut stmt = conn
         .prepare(
             "SELECT log_date, wellness_score FROM daily_summaries
-             WHERE log_date >= date('now', '-' || ?1 || ' days')
+             WHERE log_date >= ?1 AND log_date <= ?2
              ORDER BY log_date ASC",
         )
         .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map(params![days], |row| {
+    let mut points = stmt
+        .query_map(params![range.start_date, range.end_date], |row| {
             Ok(WellnessTrendPoint {
                 date: row.get(0)?,
                 wellness_score: row.get(1)?,
             })
         })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    if let Some(tag_id) = tag_id {
+        let tagged = tags::tagged_dates(&conn, tag_id)?;
+        points.retain(|p| tagged.contains(&p.date));
+    }
+    Ok(WellnessTrend {
+        start_date: range.start_date,
+        end_date: range.end_date,
+        points,
+    })
 }
 
-#[tauri::command]
-pub fn get_symptom_trends(
-    db: State<Database>,
+fn symptom_trend_points(
+    conn: &Connection,
     symptom_id: i64,
-    days: i64,
+    range: &DateRange,
 ) -> Result<Vec<SymptomTrendPoint>, String> {
-    let conn = db.conn.lock().unwrap();
+    // Fetch 29 days before the range so the first rolling averages are full.
     let mut stmt = conn
         .prepare(
-            "SELECT log_date, severity FROM symptom_logs
-             WHERE symptom_id = ?1
-               AND log_date >= date('now', '-' || ?2 || ' days')
-             ORDER BY log_date ASC",
+            "WITH days AS (
+                SELECT log_date FROM daily_summaries WHERE log_date >= date(?1, '-29 days') AND log_date <= ?2
+                UNION
+                SELECT log_date FROM symptom_logs WHERE log_date >= date(?1, '-29 days') AND log_date <= ?2
+             )
+             SELECT days.log_date, COALESCE(sl.severity, 0), COALESCE(sl.notes, '') != ''
+             FROM days
+             LEFT JOIN symptom_logs sl ON sl.log_date = days.log_date AND sl.symptom_id = ?3
+             ORDER BY days.log_date ASC",
         )
         .map_err(|e| e.to_string())?;
-    let rows = stmt
-        .query_map(params![symptom_id, days], |row| {
-            Ok(SymptomTrendPoint {
-                date: row.get::<_, String>(0)?,
-                present: row.get::<_, i64>(1)? > 0,
-            })
+    let days: Vec<(NaiveDate, String, i64, bool)> = stmt
+        .query_map(params![range.start_date, range.end_date, symptom_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?))
         })
+        .map_err(|e| e.to_string())?
+        .map(|row| {
+            let (date, severity, has_notes) = row.map_err(|e| e.to_string())?;
+            Ok((date_range::parse_date(&date)?, date, severity, has_notes))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut last_occurrence: Option<NaiveDate> = conn
+        .query_row(
+            "SELECT MAX(log_date) FROM symptom_logs
+             WHERE symptom_id = ?1 AND severity > 0 AND log_date < date(?2, '-29 days')",
+            params![symptom_id, range.start_date],
+            |r| r.get::<_, Option<String>>(0),
+        )
+        .map_err(|e| e.to_string())?
+        .map(|d| date_range::parse_date(&d))
+        .transpose()?;
+
+    let rolling = |i: usize, window: i64| {
+        let day = days[i].0;
+        let values: Vec<f64> = days[..=i]
+            .iter()
+            .rev()
+            .take_while(|(d, _, _, _)| (day - *d).num_days() < window)
+            .map(|(_, _, severity, _)| *severity as f64)
+            .collect();
+        stats::mean(&values).unwrap_or(0.0)
+    };
+
+    let mut points = Vec::new();
+    for (i, (day, date, severity, has_notes)) in days.iter().enumerate() {
+        if *severity > 0 {
+            last_occurrence = Some(*day);
+        }
+        if *date < range.start_date {
+            continue;
+        }
+        points.push(SymptomTrendPoint {
+            date: date.clone(),
+            severity: *severity,
+            present: *severity > 0,
+            has_notes: *has_notes,
+            rolling_7: rolling(i, 7),
+            rolling_30: rolling(i, 30),
+            days_since_last: last_occurrence.map(|last| (*day - last).num_days()),
+        });
+    }
+    Ok(points)
+}
+
+#[tauri::command]
+pub fn get_symptom_trends(
+    db: State<Database>,
+    symptom_id: i64,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    tag_id: Option<i64>,
+) -> Result<SymptomTrend, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    let mut points = symptom_trend_points(&conn, symptom_id, &range)?;
+    if let Some(tag_id) = tag_id {
+        let tagged = tags::tagged_dates(&conn, tag_id)?;
+        points.retain(|p| tagged.contains(&p.date));
+    }
+    Ok(SymptomTrend {
+        start_date: range.start_date,
+        end_date: range.end_date,
+        points,
+    })
+}
+
+/// Severity trends for every active symptom in one call.
+#[tauri::command]
+pub fn get_all_symptom_trends(
+    db: State<Database>,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    tag_id: Option<i64>,
+) -> Result<AllSymptomTrends, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    let tagged = tag_id.map(|id| tags::tagged_dates(&conn, id)).transpose()?;
+    let mut stmt = conn
+        .prepare("SELECT id, name, category FROM symptoms WHERE active = 1 ORDER BY sort_order")
         .map_err(|e| e.to_string())?;
-    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+    let active: Vec<(i64, String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut symptoms = Vec::new();
+    for (symptom_id, name, category) in active {
+        let mut points = symptom_trend_points(&conn, symptom_id, &range)?;
+        if let Some(tagged) = &tagged {
+            points.retain(|p| tagged.contains(&p.date));
+        }
+        symptoms.push(SymptomTrendSeries {
+            points,
+            symptom_id,
+            name,
+            category,
+        });
+    }
+    Ok(AllSymptomTrends {
+        start_date: range.start_date,
+        end_date: range.end_date,
+        symptoms,
+    })
 }
 
 #[tauri::command]
@@ -190,13 +405,15 @@ pub fn get_symptom_log(db: State<Database>, date: String) -> Result<DayLog, Stri
 
     let mut stmt = conn
         .prepare(
-            "SELECT sl.symptom_id, s.name, s.category, sl.severity, sl.notes
+            "SELECT sl.symptom_id, s.name, s.category, sl.severity, sl.notes, sl.duration_minutes, sl.onset_time
              FROM symptom_logs sl
              JOIN symptoms s ON sl.symptom_id = s.id
              WHERE sl.log_date = ?1
              ORDER BY s.sort_order",
         )
         .map_err(|e| e.to_string())?;
+    let mut intraday = load_intraday(&conn, &date)?;
+    let mut locations = load_locations(&conn, &date)?;
     let entries = stmt
         .query_map(params![date], |row| {
             Ok(SymptomLogEntry {
@@ -205,11 +422,22 @@ pub fn get_symptom_log(db: State<Database>, date: String) -> Result<DayLog, Stri
                 category: row.get(2)?,
                 severity: row.get(3)?,
                 notes: row.get(4)?,
+                intraday: Vec::new(),
+                body_locations: Vec::new(),
+                duration_minutes: row.get(5)?,
+                onset_time: row.get(6)?,
             })
         })
         .map_err(|e| e.to_string())?
         .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|mut e| {
+            e.intraday = intraday.remove(&e.symptom_id).unwrap_or_default();
+            e.body_locations = locations.remove(&e.symptom_id).unwrap_or_default();
+            e
+        })
+        .collect();
 
     let (wellness_score, notes) = conn
         .query_row(
@@ -217,7 +445,7 @@ pub fn get_symptom_log(db: State<Database>, date: String) -> Result<DayLog, Stri
             params![date],
             |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
         )
-        .unwrap_or((5, String::new()));
+        .unwrap_or((symptoms::DEFAULT_WELLNESS, String::new()));
 
     Ok(DayLog {
         date,
@@ -227,6 +455,8 @@ pub fn get_symptom_log(db: State<Database>, date: String) -> Result<DayLog, Stri
     })
 }
 
+/// Replaces the day's log; see `symptoms::save_day` for how intraday
+/// readings are kept or replaced.
 #[tauri::command]
 pub fn save_symptom_log(
     db: State<Database>,
@@ -236,25 +466,168 @@ pub fn save_symptom_log(
     notes: String,
 ) -> Result<(), String> {
     let conn = db.conn.lock().unwrap();
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    symptoms::save_day(&tx, &date, &entries, wellness, &notes, mcp_writes::SOURCE_APP)?;
+    tx.commit().map_err(|e| e.to_string())
+}
 
-    conn.execute("DELETE FROM symptom_logs WHERE log_date = ?1", params![date])
+#[tauri::command]
+pub fn get_symptom_intraday(
+    db: State<Database>,
+    symptom_id: i64,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<IntradayTrend, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT log_date, time_of_day, severity, notes FROM symptom_log_entries
+             WHERE symptom_id = ?1 AND log_date >= ?2 AND log_date <= ?3
+             ORDER BY log_date ASC",
+        )
         .map_err(|e| e.to_string())?;
+    let mut points = stmt
+        .query_map(params![symptom_id, range.start_date, range.end_date], |row| {
+            Ok(IntradayPoint {
+                date: row.get(0)?,
+                time_of_day: row.get(1)?,
+                severity: row.get(2)?,
+                notes: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    points.sort_by(|a, b| {
+        (a.date.as_str(), time_sort_key(&a.time_of_day)).cmp(&(b.date.as_str(), time_sort_key(&b.time_of_day)))
+    });
+    Ok(IntradayTrend {
+        start_date: range.start_date,
+        end_date: range.end_date,
+        points,
+    })
+}
 
-    for entry in &entries {
-        if entry.severity > 0 || !entry.notes.is_empty() {
-            conn.execute(
-                "INSERT INTO symptom_logs (log_date, symptom_id, severity, notes) VALUES (?1, ?2, ?3, ?4)",
-                params![date, entry.symptom_id, entry.severity, entry.notes],
-            )
-            .map_err(|e| e.to_string())?;
+#[tauri::command]
+pub fn get_body_locations() -> Vec<BodyLocation> {
+    BODY_LOCATIONS
+        .iter()
+        .map(|(key, label, is_joint)| BodyLocation {
+            key: key.to_string(),
+            label: label.to_string(),
+            is_joint: *is_joint,
+        })
+        .collect()
+}
+
+/// Per-day affected locations (optionally for one symptom) and how many days
+/// each location was affected in the range.
+#[tauri::command]
+pub fn get_body_location_summary(
+    db: State<Database>,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    symptom_id: Option<i64>,
+) -> Result<BodyLocationSummary, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT log_date, location FROM symptom_log_locations
+             WHERE log_date >= ?1 AND log_date <= ?2
+               AND (?3 IS NULL OR symptom_id = ?3)
+             ORDER BY log_date, location",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<(String, String)> = stmt
+        .query_map(params![range.start_date, range.end_date, symptom_id], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let is_joint = |location: &str| BODY_LOCATIONS.iter().any(|(key, _, joint)| *key == location && *joint);
+    let mut days: Vec<LocationDay> = Vec::new();
+    let mut counts: HashMap<String, i64> = HashMap::new();
+    for (date, location) in rows {
+        *counts.entry(location.clone()).or_default() += 1;
+        if days.last().map(|d| d.date != date).unwrap_or(true) {
+            days.push(LocationDay {
+                date,
+                locations: Vec::new(),
+                location_count: 0,
+                joint_count: 0,
+            });
         }
+        let day = days.last_mut().unwrap();
+        day.location_count += 1;
+        if is_joint(&location) {
+            day.joint_count += 1;
+        }
+        day.locations.push(location);
     }
 
-    conn.execute(
-        "INSERT OR REPLACE INTO daily_summaries (log_date, wellness_score, notes) VALUES (?1, ?2, ?3)",
-        params![date, wellness, notes],
-    )
-    .map_err(|e| e.to_string())?;
+    let mut frequencies: Vec<LocationFrequency> = counts
+        .into_iter()
+        .map(|(location, days)| LocationFrequency { location, days })
+        .collect();
+    frequencies.sort_by(|a, b| b.days.cmp(&a.days).then_with(|| a.location.cmp(&b.location)));
 
-    Ok(())
+    Ok(BodyLocationSummary {
+        start_date: range.start_date,
+        end_date: range.end_date,
+        days,
+        frequencies,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn log(conn: &Connection, date: &str, symptom_id: i64, severity: i64, notes: &str) {
+        conn.execute(
+            "INSERT INTO symptom_logs (log_date, symptom_id, severity, notes) VALUES (?1, ?2, ?3, ?4)",
+            params![date, symptom_id, severity, notes],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn trend_points_cover_logged_days_with_rolling_averages() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        log(&conn, "2024-01-01", 1, 4, ""); // before the 29-day lead-in
+        log(&conn, "2024-01-20", 2, 3, "");
+        log(&conn, "2024-02-01", 2, 3, "");
+        log(&conn, "2024-02-05", 1, 6, "after a long walk");
+        log(&conn, "2024-02-08", 2, 3, "");
+
+        let range = DateRange { start_date: "2024-02-01".to_string(), end_date: "2024-02-10".to_string() };
+        let points = symptom_trend_points(&conn, 1, &range).unwrap();
+        let days: Vec<(&str, i64, bool, bool)> =
+            points.iter().map(|p| (p.date.as_str(), p.severity, p.present, p.has_notes)).collect();
+        assert_eq!(
+            days,
+            [("2024-02-01", 0, false, false), ("2024-02-05", 6, true, true), ("2024-02-08", 0, false, false)]
+        );
+        let averages: Vec<(f64, f64, Option<i64>)> =
+            points.iter().map(|p| (p.rolling_7, p.rolling_30, p.days_since_last)).collect();
+        // Feb 1 is exactly 7 days before Feb 8, so it falls out of that 7-day window
+        assert_eq!(averages, [(0.0, 0.0, Some(31)), (3.0, 2.0, Some(0)), (3.0, 1.5, Some(3))]);
+    }
+
+    #[test]
+    fn trend_points_without_any_occurrence_have_no_days_since_last() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        log(&conn, "2024-02-01", 2, 3, "");
+        let range = DateRange { start_date: "2024-02-01".to_string(), end_date: "2024-02-10".to_string() };
+        let points = symptom_trend_points(&conn, 1, &range).unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].days_since_last, None);
+    }
 }
@@ -0,0 +1,236 @@
+use crate::date_range;
+use crate::db::Database;
+use crate::tags::{self, Tag, TagComparison};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SymptomLogTags {
+    pub symptom_id: i64,
+    pub tags: Vec<Tag>,
+}
+
+/// Replaces one owner's tag links in a single transaction: `delete_sql` runs
+/// with the owner's key values, then `insert_sql` once per tag with the key
+/// values followed by the tag id.
+fn replace_links(
+    conn: &Connection,
+    delete_sql: &str,
+    insert_sql: &str,
+    owner: &[&dyn rusqlite::ToSql],
+    tag_ids: &[i64],
+) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    for tag_id in tag_ids {
+        tags::get_tag(&tx, *tag_id)?;
+    }
+    tx.execute(delete_sql, owner).map_err(|e| e.to_string())?;
+    for tag_id in tag_ids {
+        let mut values: Vec<&dyn rusqlite::ToSql> = owner.to_vec();
+        values.push(tag_id);
+        tx.execute(insert_sql, values.as_slice()).map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_tags(db: State<Database>) -> Result<Vec<Tag>, String> {
+    let conn = db.conn.lock().unwrap();
+    tags::all_tags(&conn)
+}
+
+#[tauri::command]
+pub fn save_tag(db: State<Database>, tag: Tag) -> Result<i64, String> {
+    let name = tag.name.trim();
+    if name.is_empty() {
+        return Err("Tag name must not be empty".to_string());
+    }
+    let conn = db.conn.lock().unwrap();
+    // The name check and the write go together so a concurrent save can't slip a duplicate in between
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let duplicate: bool = tx
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM tags WHERE name = ?1 AND id IS NOT ?2",
+            params![name, tag.id],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if duplicate {
+        return Err(format!("A tag named \"{}\" already exists", name));
+    }
+    let id = if let Some(id) = tag.id {
+        tx.execute(
+            "UPDATE tags SET name = ?1, color = ?2 WHERE id = ?3",
+            params![name, tag.color, id],
+        )
+        .map_err(|e| e.to_string())?;
+        id
+    } else {
+        tx.execute(
+            "INSERT INTO tags (name, color) VALUES (?1, ?2)",
+            params![name, tag.color],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.last_insert_rowid()
+    };
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn delete_tag(db: State<Database>, id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().unwrap();
+    conn.execute("DELETE FROM tags WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_day_tags(db: State<Database>, date: String) -> Result<Vec<Tag>, String> {
+    let conn = db.conn.lock().unwrap();
+    tags::day_tags(&conn, &date)
+}
+
+#[tauri::command]
+pub fn set_day_tags(db: State<Database>, date: String, tag_ids: Vec<i64>) -> Result<(), String> {
+    date_range::parse_date(&date)?;
+    let conn = db.conn.lock().unwrap();
+    replace_links(
+        &conn,
+        "DELETE FROM day_tags WHERE log_date = ?1",
+        "INSERT OR IGNORE INTO day_tags (log_date, tag_id) VALUES (?1, ?2)",
+        &[&date],
+        &tag_ids,
+    )
+}
+
+#[tauri::command]
+pub fn get_lab_session_tags(db: State<Database>, session_id: i64) -> Result<Vec<Tag>, String> {
+    let conn = db.conn.lock().unwrap();
+    tags::lab_session_tags(&conn, session_id)
+}
+
+#[tauri::command]
+pub fn set_lab_session_tags(db: State<Database>, session_id: i64, tag_ids: Vec<i64>) -> Result<(), String> {
+    let conn = db.conn.lock().unwrap();
+    replace_links(
+        &conn,
+        "DELETE FROM lab_session_tags WHERE session_id = ?1",
+        "INSERT OR IGNORE INTO lab_session_tags (session_id, tag_id) VALUES (?1, ?2)",
+        &[&session_id],
+        &tag_ids,
+    )
+}
+
+#[tauri::command]
+pub fn get_symptom_log_tags(db: State<Database>, date: String) -> Result<Vec<SymptomLogTags>, String> {
+    let conn = db.conn.lock().unwrap();
+    let mut by_symptom: BTreeMap<i64, Vec<Tag>> = BTreeMap::new();
+    for (symptom_id, tag) in tags::symptom_log_tags(&conn, &date)? {
+        by_symptom.entry(symptom_id).or_default().push(tag);
+    }
+    Ok(by_symptom
+        .into_iter()
+        .map(|(symptom_id, tags)| SymptomLogTags { symptom_id, tags })
+        .collect())
+}
+
+/// Tags only attach to a symptom logged that day; saving the day drops tags
+/// on symptoms it no longer logs, so they would be lost anyway.
+fn check_symptom_logged(conn: &Connection, date: &str, symptom_id: i64) -> Result<(), String> {
+    let logged: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM symptom_logs WHERE log_date = ?1 AND symptom_id = ?2",
+            params![date, symptom_id],
+            |r| r.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if !logged {
+        return Err(format!("Symptom {} has no log on {} to tag", symptom_id, date));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_symptom_log_tags(
+    db: State<Database>,
+    date: String,
+    symptom_id: i64,
+    tag_ids: Vec<i64>,
+) -> Result<(), String> {
+    date_range::parse_date(&date)?;
+    let conn = db.conn.lock().unwrap();
+    if !tag_ids.is_empty() {
+        check_symptom_logged(&conn, &date, symptom_id)?;
+    }
+    replace_links(
+        &conn,
+        "DELETE FROM symptom_log_tags WHERE log_date = ?1 AND symptom_id = ?2",
+        "INSERT OR IGNORE INTO symptom_log_tags (log_date, symptom_id, tag_id) VALUES (?1, ?2, ?3)",
+        &[&date, &symptom_id],
+        &tag_ids,
+    )
+}
+
+/// Mean wellness and symptom severity on days carrying the tag versus the
+/// rest of the logged days in the range.
+#[tauri::command]
+pub fn get_tag_comparison(
+    db: State<Database>,
+    tag_id: i64,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<TagComparison, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    tags::compare_tag(&conn, tag_id, &range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn day_links(conn: &Connection, date: &str, tag_ids: &[i64]) -> Result<(), String> {
+        replace_links(
+            conn,
+            "DELETE FROM day_tags WHERE log_date = ?1",
+            "INSERT OR IGNORE INTO day_tags (log_date, tag_id) VALUES (?1, ?2)",
+            &[&date],
+            tag_ids,
+        )
+    }
+
+    #[test]
+    fn links_are_replaced_whole_or_not_at_all() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        conn.execute_batch("INSERT INTO tags (name) VALUES ('travel'), ('stress'), ('infusion');").unwrap();
+        let names = |conn: &Connection| -> Vec<String> {
+            tags::day_tags(conn, "2024-03-01").unwrap().into_iter().map(|t| t.name).collect()
+        };
+        day_links(&conn, "2024-03-01", &[1, 2]).unwrap();
+        assert_eq!(names(&conn), ["stress", "travel"]);
+
+        // An unknown tag fails the whole replacement
+        assert!(day_links(&conn, "2024-03-01", &[3, 99]).is_err());
+        assert_eq!(names(&conn), ["stress", "travel"]);
+
+        day_links(&conn, "2024-03-01", &[3]).unwrap();
+        assert_eq!(names(&conn), ["infusion"]);
+    }
+
+    #[test]
+    fn only_logged_symptoms_take_tags() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        conn.execute("INSERT INTO symptom_logs (log_date, symptom_id, severity) VALUES ('2024-03-01', 1, 4)", [])
+            .unwrap();
+        check_symptom_logged(&conn, "2024-03-01", 1).unwrap();
+        assert!(check_symptom_logged(&conn, "2024-03-01", 2).is_err());
+        assert!(check_symptom_logged(&conn, "2024-03-02", 1).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use crate::date_range;
+use crate::db::Database;
+use crate::vitals::{self, VitalRange, VitalReading, VitalTrendPoint};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VitalReadings {
+    pub start_date: String,
+    pub end_date: String,
+    pub readings: Vec<VitalReading>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VitalTrend {
+    pub start_date: String,
+    pub end_date: String,
+    pub points: Vec<VitalTrendPoint>,
+}
+
+#[tauri::command]
+pub fn get_vitals(
+    db: State<Database>,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<VitalReadings, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    let readings = vitals::query_readings(&conn, &range.start_date, &range.end_date)?;
+    Ok(VitalReadings {
+        start_date: range.start_date,
+        end_date: range.end_date,
+        readings,
+    })
+}
+
+#[tauri::command]
+pub fn save_vital_reading(db: State<Database>, reading: VitalReading) -> Result<i64, String> {
+    vitals::validate_reading(&reading)?;
+    let conn = db.conn.lock().unwrap();
+    if let Some(id) = reading.id {
+        conn.execute(
+            "UPDATE vital_readings SET measured_at = ?1, systolic_bp = ?2, diastolic_bp = ?3, heart_rate = ?4,
+                    temperature_c = ?5, weight_kg = ?6, spo2 = ?7, notes = ?8 WHERE id = ?9",
+            params![
+                reading.measured_at,
+                reading.systolic_bp,
+                reading.diastolic_bp,
+                reading.heart_rate,
+                reading.temperature_c,
+                reading.weight_kg,
+                reading.spo2,
+                reading.notes,
+                id,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(id)
+    } else {
+        conn.execute(
+            "INSERT INTO vital_readings (measured_at, systolic_bp, diastolic_bp, heart_rate, temperature_c, weight_kg, spo2, notes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                reading.measured_at,
+                reading.systolic_bp,
+                reading.diastolic_bp,
+                reading.heart_rate,
+                reading.temperature_c,
+                reading.weight_kg,
+                reading.spo2,
+                reading.notes,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(conn.last_insert_rowid())
+    }
+}
+
+#[tauri::command]
+pub fn delete_vital_reading(db: State<Database>, id: i64) -> Result<(), String> {
+    let conn = db.conn.lock().unwrap();
+    conn.execute("DELETE FROM vital_readings WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_vital_ranges(db: State<Database>) -> Result<Vec<VitalRange>, String> {
+    let conn = db.conn.lock().unwrap();
+    Ok(vitals::ranges(&conn))
+}
+
+#[tauri::command]
+pub fn save_vital_range(
+    db: State<Database>,
+    kind: String,
+    low: Option<f64>,
+    high: Option<f64>,
+) -> Result<(), String> {
+    if !vitals::is_vital_kind(&kind) {
+        return Err(format!("Unknown vital sign: {}", kind));
+    }
+    vitals::validate_range(low, high)?;
+    let conn = db.conn.lock().unwrap();
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2), (?3, ?4)",
+        params![
+            format!("vital_{}_low", kind),
+            low.map(|v| v.to_string()).unwrap_or_default(),
+            format!("vital_{}_high", kind),
+            high.map(|v| v.to_string()).unwrap_or_default(),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_vital_trends(
+    db: State<Database>,
+    kind: String,
+    days: Option<i64>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<VitalTrend, String> {
+    let conn = db.conn.lock().unwrap();
+    let range = date_range::resolve(&conn, days, start_date.as_deref(), end_date.as_deref())?;
+    let points = vitals::query_trends(&conn, &kind, &range)?;
+    Ok(VitalTrend {
+        start_date: range.start_date,
+        end_date: range.end_date,
+        points,
+    })
+}
@@ -0,0 +1,94 @@
+// Query windows for trend and history lookups. Callers either give explicit
+// start/end dates or a "last N days" shorthand; both resolve to a DateRange
+// that is echoed back in responses.
+
+use chrono::NaiveDate;
+use rusqlite::{params, Connection};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct DateRange {
+    pub start_date: String,
+    pub end_date: String,
+}
+
+pub fn parse_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| format!("Invalid date (expected YYYY-MM-DD): {}", s))
+}
+
+/// Resolves the effective window. `end_date` defaults to today; `start_date`
+/// defaults to `days` before the end. At least one of `start_date` and
+/// `days` is required. "Today" comes from SQLite's clock so it matches the
+/// stored `date('now')` defaults.
+pub fn resolve(
+    conn: &Connection,
+    days: Option<i64>,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+) -> Result<DateRange, String> {
+    if let Some(d) = days {
+        if d < 0 {
+            return Err("days must not be negative".to_string());
+        }
+    }
+    let end_date = match end_date {
+        Some(end) => {
+            parse_date(end)?;
+            end.to_string()
+        }
+        None => conn
+            .query_row("SELECT date('now')", [], |r| r.get(0))
+            .map_err(|e| e.to_string())?,
+    };
+    let start_date = match (start_date, days) {
+        (Some(start), _) => {
+            parse_date(start)?;
+            start.to_string()
+        }
+        (None, Some(days)) => conn
+            .query_row(
+                "SELECT date(?1, '-' || ?2 || ' days')",
+                params![end_date, days],
+                |r| r.get(0),
+            )
+            .map_err(|e| e.to_string())?,
+        (None, None) => return Err("Provide either days or a start date".to_string()),
+    };
+    if start_date > end_date {
+        return Err(format!("Start date {} is after end date {}", start_date, end_date));
+    }
+    Ok(DateRange { start_date, end_date })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolved(days: Option<i64>, start: Option<&str>, end: Option<&str>) -> Result<(String, String), String> {
+        let conn = Connection::open_in_memory().unwrap();
+        resolve(&conn, days, start, end).map(|r| (r.start_date, r.end_date))
+    }
+
+    #[test]
+    fn days_count_back_from_the_end_date() {
+        let range = resolved(Some(30), None, Some("2024-03-15")).unwrap();
+        assert_eq!(range, ("2024-02-14".to_string(), "2024-03-15".to_string()));
+        // An explicit start wins over days
+        let range = resolved(Some(30), Some("2024-03-01"), Some("2024-03-15")).unwrap();
+        assert_eq!(range.0, "2024-03-01");
+
+        let conn = Connection::open_in_memory().unwrap();
+        let today: String = conn.query_row("SELECT date('now')", [], |r| r.get(0)).unwrap();
+        assert_eq!(resolved(Some(0), None, None).unwrap(), (today.clone(), today));
+    }
+
+    #[test]
+    fn invalid_ranges_are_rejected() {
+        assert!(resolved(None, None, Some("2024-03-15")).is_err());
+        assert!(resolved(Some(-1), None, None).is_err());
+        assert!(resolved(None, Some("2024-02-30"), None).is_err());
+        assert!(resolved(Some(7), None, Some("15/03/2024")).is_err());
+        assert!(resolved(None, Some("2024-03-16"), Some("2024-03-15")).is_err());
+    }
+}
@@ -1,18 +1,26 @@
 use rusqlite::{Connection, Result, params};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
+/// Stored in `PRAGMA user_version`. Bump it when a migration changes the
+/// schema in a way an older MCP server binary would misread.
+pub const SCHEMA_VERSION: i64 = 1;
+
 pub struct Database {
     pub conn: Mutex<Connection>,
 }
 
 impl Database {
     pub fn new() -> Result<Self> {
-        let path = db_path();
+        Self::open(&db_path())
+    }
+
+    /// Opens (creating and migrating if needed) the database at `path`.
+    pub fn open(path: &Path) -> Result<Self> {
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent).expect("Failed to create data directory");
         }
-        let conn = Connection::open(&path)?;
+        let conn = Connection::open(path)?;
         conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")?;
         let db = Database { conn: Mutex::new(conn) };
         db.migrate()?;
@@ -63,6 +71,22 @@ impl Database {
                 UNIQUE(log_date, symptom_id)
             );
 
+            CREATE TABLE IF NOT EXISTS symptom_log_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                log_date TEXT NOT NULL,
+                symptom_id INTEGER NOT NULL REFERENCES symptoms(id) ON DELETE CASCADE,
+                time_of_day TEXT NOT NULL,
+                severity INTEGER NOT NULL DEFAULT 0,
+                notes TEXT NOT NULL DEFAULT ''
+            );
+
+            CREATE TABLE IF NOT EXISTS symptom_log_locations (
+                log_date TEXT NOT NULL,
+                symptom_id INTEGER NOT NULL REFERENCES symptoms(id) ON DELETE CASCADE,
+                location TEXT NOT NULL,
+                PRIMARY KEY (log_date, symptom_id, location)
+            );
+
             CREATE TABLE IF NOT EXISTS daily_summaries (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 log_date TEXT NOT NULL UNIQUE,
@@ -82,19 +106,151 @@ impl Database {
                 clinical TEXT NOT NULL DEFAULT ''
             );
 
+            CREATE TABLE IF NOT EXISTS medication_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                medication TEXT NOT NULL,
+                event_date TEXT NOT NULL,
+                event_type TEXT NOT NULL DEFAULT 'start',
+                dose TEXT NOT NULL DEFAULT '',
+                notes TEXT NOT NULL DEFAULT '',
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS warfarin_doses (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                week_start TEXT NOT NULL,
+                weekly_dose_mg REAL NOT NULL,
+                notes TEXT NOT NULL DEFAULT ''
+            );
+
+            CREATE TABLE IF NOT EXISTS vital_readings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                measured_at TEXT NOT NULL,
+                systolic_bp REAL,
+                diastolic_bp REAL,
+                heart_rate REAL,
+                temperature_c REAL,
+                weight_kg REAL,
+                spo2 REAL,
+                notes TEXT NOT NULL DEFAULT '',
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS flares (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                start_date TEXT NOT NULL,
+                end_date TEXT,
+                severity INTEGER NOT NULL DEFAULT 0,
+                triggers TEXT NOT NULL DEFAULT '',
+                treatment_notes TEXT NOT NULL DEFAULT '',
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS flare_systems (
+                flare_id INTEGER NOT NULL REFERENCES flares(id) ON DELETE CASCADE,
+                category TEXT NOT NULL,
+                PRIMARY KEY (flare_id, category)
+            );
+
             CREATE TABLE IF NOT EXISTS settings (
                 key TEXT PRIMARY KEY,
                 value TEXT NOT NULL DEFAULT ''
             );
 
+            CREATE TABLE IF NOT EXISTS tags (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+                color TEXT NOT NULL DEFAULT '',
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            -- Days and symptom logs are linked by date (and symptom) rather than row
+            -- id, since saving a day replaces its rows.
+            CREATE TABLE IF NOT EXISTS day_tags (
+                log_date TEXT NOT NULL,
+                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                PRIMARY KEY (log_date, tag_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS lab_session_tags (
+                session_id INTEGER NOT NULL REFERENCES lab_sessions(id) ON DELETE CASCADE,
+                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                PRIMARY KEY (session_id, tag_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS symptom_log_tags (
+                log_date TEXT NOT NULL,
+                symptom_id INTEGER NOT NULL REFERENCES symptoms(id) ON DELETE CASCADE,
+                tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                PRIMARY KEY (log_date, symptom_id, tag_id)
+            );
+
+            -- Journal of writes made through the MCP server, for review and undo.
+            -- Day writes keep the day's prior log as JSON; lab session writes
+            -- are undone by deleting the session.
+            CREATE TABLE IF NOT EXISTS mcp_writes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                tool TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                record_date TEXT NOT NULL,
+                session_id INTEGER,
+                previous_day TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                undone_at TEXT
+            );
+
             INSERT OR IGNORE INTO settings (key, value) VALUES ('mcp_enabled', 'true');
+            INSERT OR IGNORE INTO settings (key, value) VALUES ('mcp_write_enabled', 'false');
+            INSERT OR IGNORE INTO settings (key, value) VALUES ('mcp_policy', '{}');
+            INSERT OR IGNORE INTO settings (key, value) VALUES ('mcp_access_log_days', '90');
+            INSERT OR IGNORE INTO settings (key, value) VALUES ('mcp_http_port', '8765');
+            INSERT OR IGNORE INTO settings (key, value) VALUES ('mcp_http_token', lower(hex(randomblob(32))));
+            INSERT OR IGNORE INTO settings (key, value) VALUES ('symptom_day_aggregate', 'max');
+            INSERT OR IGNORE INTO settings (key, value) VALUES ('inr_target_low', '2.0');
+            INSERT OR IGNORE INTO settings (key, value) VALUES ('inr_target_high', '3.0');
 
             CREATE INDEX IF NOT EXISTS idx_lab_results_session ON lab_results(session_id);
             CREATE INDEX IF NOT EXISTS idx_lab_results_test ON lab_results(test_name);
             CREATE INDEX IF NOT EXISTS idx_symptom_logs_date ON symptom_logs(log_date);
+            CREATE INDEX IF NOT EXISTS idx_symptom_log_entries_date ON symptom_log_entries(log_date, symptom_id);
+            CREATE INDEX IF NOT EXISTS idx_medication_events_date ON medication_events(event_date);
+            CREATE INDEX IF NOT EXISTS idx_vital_readings_time ON vital_readings(measured_at);
+            CREATE INDEX IF NOT EXISTS idx_flares_start ON flares(start_date);
+            CREATE INDEX IF NOT EXISTS idx_day_tags_tag ON day_tags(tag_id);
+            CREATE INDEX IF NOT EXISTS idx_lab_session_tags_tag ON lab_session_tags(tag_id);
+            CREATE INDEX IF NOT EXISTS idx_symptom_log_tags_tag ON symptom_log_tags(tag_id);
             "
         )?;
 
+        // Columns added after the initial schema
+        add_column_if_missing(&conn, "symptom_logs", "duration_minutes", "INTEGER")?;
+        add_column_if_missing(&conn, "symptom_logs", "onset_time", "TEXT")?;
+        // Who last wrote the row: "app" or "mcp"
+        add_column_if_missing(&conn, "lab_sessions", "source", "TEXT NOT NULL DEFAULT 'app'")?;
+        add_column_if_missing(&conn, "symptom_logs", "source", "TEXT NOT NULL DEFAULT 'app'")?;
+        add_column_if_missing(&conn, "daily_summaries", "source", "TEXT NOT NULL DEFAULT 'app'")?;
+
+        // Full-text index over free-text notes, kept in sync by triggers.
+        // Existing notes are indexed once when the table is first created.
+        let has_notes_fts: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'notes_fts'",
+            [],
+            |r| r.get(0),
+        )?;
+        conn.execute_batch(NOTES_FTS_SCHEMA)?;
+        if !has_notes_fts {
+            conn.execute_batch(
+                "
+                INSERT INTO notes_fts (notes, source, source_id, note_date)
+                    SELECT notes, 'lab_session', id, test_date FROM lab_sessions WHERE notes != '';
+                INSERT INTO notes_fts (notes, source, source_id, note_date)
+                    SELECT notes, 'symptom_log', id, log_date FROM symptom_logs WHERE notes != '';
+                INSERT INTO notes_fts (notes, source, source_id, note_date)
+                    SELECT notes, 'daily_summary', id, log_date FROM daily_summaries WHERE notes != '';
+                "
+            )?;
+        }
+
         // Seed default symptoms if table is empty
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM symptoms", [], |r| r.get(0))?;
         if count == 0 {
@@ -126,10 +282,84 @@ impl Database {
             }
         }
 
+        // Never lowered, so an older app build doesn't hide a newer schema
+        if schema_version(&conn)? < SCHEMA_VERSION {
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+
         Ok(())
     }
 }
 
+// Each source table re-indexes a row on insert/update and drops it on delete.
+// Daily summaries are matched by date as well as id, because they are saved
+// with INSERT OR REPLACE, which does not fire delete triggers.
+const NOTES_FTS_SCHEMA: &str = "
+    CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+        notes,
+        source UNINDEXED,
+        source_id UNINDEXED,
+        note_date UNINDEXED,
+        tokenize = 'porter unicode61'
+    );
+
+    CREATE TRIGGER IF NOT EXISTS lab_sessions_fts_insert AFTER INSERT ON lab_sessions BEGIN
+        INSERT INTO notes_fts (notes, source, source_id, note_date)
+            SELECT new.notes, 'lab_session', new.id, new.test_date WHERE new.notes != '';
+    END;
+    CREATE TRIGGER IF NOT EXISTS lab_sessions_fts_update AFTER UPDATE ON lab_sessions BEGIN
+        DELETE FROM notes_fts WHERE source = 'lab_session' AND source_id = old.id;
+        INSERT INTO notes_fts (notes, source, source_id, note_date)
+            SELECT new.notes, 'lab_session', new.id, new.test_date WHERE new.notes != '';
+    END;
+    CREATE TRIGGER IF NOT EXISTS lab_sessions_fts_delete AFTER DELETE ON lab_sessions BEGIN
+        DELETE FROM notes_fts WHERE source = 'lab_session' AND source_id = old.id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS symptom_logs_fts_insert AFTER INSERT ON symptom_logs BEGIN
+        INSERT INTO notes_fts (notes, source, source_id, note_date)
+            SELECT new.notes, 'symptom_log', new.id, new.log_date WHERE new.notes != '';
+    END;
+    CREATE TRIGGER IF NOT EXISTS symptom_logs_fts_update AFTER UPDATE ON symptom_logs BEGIN
+        DELETE FROM notes_fts WHERE source = 'symptom_log' AND source_id = old.id;
+        INSERT INTO notes_fts (notes, source, source_id, note_date)
+            SELECT new.notes, 'symptom_log', new.id, new.log_date WHERE new.notes != '';
+    END;
+    CREATE TRIGGER IF NOT EXISTS symptom_logs_fts_delete AFTER DELETE ON symptom_logs BEGIN
+        DELETE FROM notes_fts WHERE source = 'symptom_log' AND source_id = old.id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS daily_summaries_fts_insert AFTER INSERT ON daily_summaries BEGIN
+        DELETE FROM notes_fts WHERE source = 'daily_summary' AND note_date = new.log_date;
+        INSERT INTO notes_fts (notes, source, source_id, note_date)
+            SELECT new.notes, 'daily_summary', new.id, new.log_date WHERE new.notes != '';
+    END;
+    CREATE TRIGGER IF NOT EXISTS daily_summaries_fts_update AFTER UPDATE ON daily_summaries BEGIN
+        DELETE FROM notes_fts WHERE source = 'daily_summary' AND source_id = old.id;
+        INSERT INTO notes_fts (notes, source, source_id, note_date)
+            SELECT new.notes, 'daily_summary', new.id, new.log_date WHERE new.notes != '';
+    END;
+    CREATE TRIGGER IF NOT EXISTS daily_summaries_fts_delete AFTER DELETE ON daily_summaries BEGIN
+        DELETE FROM notes_fts WHERE source = 'daily_summary' AND source_id = old.id;
+    END;
+";
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let exists: bool = conn.query_row(
+        &format!("SELECT COUNT(*) > 0 FROM pragma_table_info('{}') WHERE name = ?1", table),
+        [column],
+        |r| r.get(0),
+    )?;
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl))?;
+    }
+    Ok(())
+}
+
+pub fn schema_version(conn: &Connection) -> Result<i64> {
+    conn.pragma_query_value(None, "user_version", |r| r.get(0))
+}
+
 pub fn db_path() -> PathBuf {
     let base = dirs::data_local_dir().expect("Could not determine local data directory");
     base.join("symptom-test-tracker").join("tracker.db")
@@ -0,0 +1,477 @@
+// Flare episodes and the labs drawn during them. An open flare (no end
+// date) runs to today.
+
+use crate::date_range::{parse_date, DateRange};
+use crate::stats;
+use rusqlite::{params, Connection};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct Flare {
+    pub id: Option<i64>,
+    pub start_date: String,
+    pub end_date: Option<String>,
+    pub severity: i64,
+    pub affected_systems: Vec<String>,
+    pub triggers: String,
+    pub treatment_notes: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FlareLabResult {
+    pub test_name: String,
+    pub panel: String,
+    pub value: Option<f64>,
+    pub text_value: String,
+    pub unit: String,
+    pub flag: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FlareLabSession {
+    pub session_id: i64,
+    pub test_date: String,
+    pub lab_name: String,
+    pub results: Vec<FlareLabResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FlareWithLabs {
+    pub flare: Flare,
+    pub labs: Vec<FlareLabSession>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FlareCandidate {
+    pub start_date: String,
+    pub end_date: String,
+    pub flagged_days: i64,
+    pub peak_burden: i64,
+    pub mean_wellness: Option<f64>,
+    pub affected_systems: Vec<String>,
+    pub overlaps_existing: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FlareSuggestions {
+    pub start_date: String,
+    pub end_date: String,
+    pub burden_threshold: f64,
+    pub wellness_threshold: Option<f64>,
+    pub candidates: Vec<FlareCandidate>,
+}
+
+fn load_systems(conn: &Connection, flare_id: i64) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT category FROM flare_systems WHERE flare_id = ?1 ORDER BY category")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![flare_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Flares overlapping `start_date..=end_date`, oldest first. An open flare
+/// (no end date) overlaps everything after its start.
+pub fn query_flares(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<Flare>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, start_date, end_date, severity, triggers, treatment_notes FROM flares
+             WHERE start_date <= ?2 AND COALESCE(end_date, '9999-12-31') >= ?1
+             ORDER BY start_date ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let mut flares = stmt
+        .query_map(params![start_date, end_date], |row| {
+            Ok(Flare {
+                id: Some(row.get(0)?),
+                start_date: row.get(1)?,
+                end_date: row.get(2)?,
+                severity: row.get(3)?,
+                affected_systems: Vec::new(),
+                triggers: row.get(4)?,
+                treatment_notes: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for flare in &mut flares {
+        if let Some(id) = flare.id {
+            flare.affected_systems = load_systems(conn, id)?;
+        }
+    }
+    Ok(flares)
+}
+
+/// Lab sessions drawn between the flare's start and end (or today, if the
+/// flare is still open).
+pub fn labs_during(conn: &Connection, flare: &Flare) -> Result<Vec<FlareLabSession>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, test_date, lab_name FROM lab_sessions
+             WHERE test_date >= ?1 AND test_date <= COALESCE(?2, date('now'))
+             ORDER BY test_date ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let sessions: Vec<(i64, String, String)> = stmt
+        .query_map(params![flare.start_date, flare.end_date], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut out = Vec::new();
+    for (session_id, test_date, lab_name) in sessions {
+        let mut rstmt = conn
+            .prepare(
+                "SELECT test_name, panel, value, text_value, unit, flag
+                 FROM lab_results WHERE session_id = ?1 ORDER BY panel, test_name",
+            )
+            .map_err(|e| e.to_string())?;
+        let results = rstmt
+            .query_map(params![session_id], |row| {
+                Ok(FlareLabResult {
+                    test_name: row.get(0)?,
+                    panel: row.get(1)?,
+                    value: row.get(2)?,
+                    text_value: row.get(3)?,
+                    unit: row.get(4)?,
+                    flag: row.get(5)?,
+                })
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?;
+        out.push(FlareLabSession {
+            session_id,
+            test_date,
+            lab_name,
+            results,
+        });
+    }
+    Ok(out)
+}
+
+pub fn query_flares_with_labs(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+) -> Result<Vec<FlareWithLabs>, String> {
+    query_flares(conn, start_date, end_date)?
+        .into_iter()
+        .map(|flare| {
+            let labs = labs_during(conn, &flare)?;
+            Ok(FlareWithLabs { flare, labs })
+        })
+        .collect()
+}
+
+pub const MAX_SEVERITY: i64 = 10;
+
+fn validate_flare(flare: &Flare) -> Result<(), String> {
+    let start = parse_date(&flare.start_date)?;
+    if let Some(end) = &flare.end_date {
+        if parse_date(end)? < start {
+            return Err("Flare end date must not be before its start date".to_string());
+        }
+    }
+    if !(0..=MAX_SEVERITY).contains(&flare.severity) {
+        return Err(format!("Flare severity must be between 0 and {}, got {}", MAX_SEVERITY, flare.severity));
+    }
+    Ok(())
+}
+
+/// Inserts or updates a flare and replaces its affected systems, which must
+/// match existing symptom categories.
+pub fn save_flare(conn: &Connection, flare: &Flare) -> Result<i64, String> {
+    validate_flare(flare)?;
+    for system in &flare.affected_systems {
+        let known: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM symptoms WHERE category = ?1",
+                params![system],
+                |r| r.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !known {
+            return Err(format!("Unknown symptom category: {}", system));
+        }
+    }
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let flare_id = if let Some(id) = flare.id {
+        tx.execute(
+            "UPDATE flares SET start_date = ?1, end_date = ?2, severity = ?3, triggers = ?4, treatment_notes = ?5 WHERE id = ?6",
+            params![flare.start_date, flare.end_date, flare.severity, flare.triggers, flare.treatment_notes, id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM flare_systems WHERE flare_id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+        id
+    } else {
+        tx.execute(
+            "INSERT INTO flares (start_date, end_date, severity, triggers, treatment_notes) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![flare.start_date, flare.end_date, flare.severity, flare.triggers, flare.treatment_notes],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.last_insert_rowid()
+    };
+
+    for system in &flare.affected_systems {
+        tx.execute(
+            "INSERT OR IGNORE INTO flare_systems (flare_id, category) VALUES (?1, ?2)",
+            params![flare_id, system],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(flare_id)
+}
+
+/// Proposes flare windows from the logs within `range`. A day is flagged when
+/// its symptom burden (sum of severities) reaches twice the median daily
+/// burden, or its wellness score falls two or more points below the median.
+/// Flagged days separated by at most `max_gap_days` are merged, and windows
+/// shorter than `min_days` are dropped.
+pub fn suggest_flares(
+    conn: &Connection,
+    range: &DateRange,
+    min_days: i64,
+    max_gap_days: i64,
+) -> Result<FlareSuggestions, String> {
+    if min_days < 1 {
+        return Err("min_days must be at least 1".to_string());
+    }
+    if max_gap_days < 0 {
+        return Err("max_gap_days must not be negative".to_string());
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "WITH d AS (
+                SELECT log_date FROM symptom_logs WHERE log_date >= ?1 AND log_date <= ?2
+                UNION
+                SELECT log_date FROM daily_summaries WHERE log_date >= ?1 AND log_date <= ?2
+             )
+             SELECT d.log_date,
+                    COALESCE((SELECT SUM(severity) FROM symptom_logs sl WHERE sl.log_date = d.log_date), 0),
+                    (SELECT wellness_score FROM daily_summaries ds WHERE ds.log_date = d.log_date)
+             FROM d ORDER BY d.log_date ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let logged: Vec<(String, i64, Option<i64>)> = stmt
+        .query_map(params![range.start_date, range.end_date], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let burdens: Vec<f64> = logged.iter().map(|(_, b, _)| *b as f64).collect();
+    let wellness: Vec<f64> = logged.iter().filter_map(|(_, _, w)| w.map(|w| w as f64)).collect();
+    let burden_threshold = (stats::median(&burdens).unwrap_or(0.0) * 2.0).max(1.0);
+    let wellness_threshold = stats::median(&wellness).map(|m| m - 2.0);
+
+    let flagged: Vec<&(String, i64, Option<i64>)> = logged
+        .iter()
+        .filter(|(_, burden, w)| {
+            *burden as f64 >= burden_threshold
+                || matches!((w, wellness_threshold), (Some(w), Some(t)) if (*w as f64) <= t)
+        })
+        .collect();
+
+    let mut windows: Vec<Vec<&(String, i64, Option<i64>)>> = Vec::new();
+    for day in flagged {
+        let extends = match windows.last().and_then(|w| w.last()) {
+            Some(prev) => (parse_date(&day.0)? - parse_date(&prev.0)?).num_days() <= max_gap_days + 1,
+            None => false,
+        };
+        if extends {
+            windows.last_mut().unwrap().push(day);
+        } else {
+            windows.push(vec![day]);
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for window in windows {
+        let start_date = window[0].0.clone();
+        let end_date = window[window.len() - 1].0.clone();
+        if (parse_date(&end_date)? - parse_date(&start_date)?).num_days() + 1 < min_days {
+            continue;
+        }
+        let window_wellness: Vec<f64> = logged
+            .iter()
+            .filter(|(d, _, _)| *d >= start_date && *d <= end_date)
+            .filter_map(|(_, _, w)| w.map(|w| w as f64))
+            .collect();
+
+        let mut sys_stmt = conn
+            .prepare(
+                "SELECT DISTINCT s.category FROM symptom_logs sl
+                 JOIN symptoms s ON sl.symptom_id = s.id
+                 WHERE sl.log_date >= ?1 AND sl.log_date <= ?2 AND sl.severity > 0 AND s.category != ''
+                 ORDER BY s.category",
+            )
+            .map_err(|e| e.to_string())?;
+        let affected_systems = sys_stmt
+            .query_map(params![start_date, end_date], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| e.to_string())?;
+
+        let overlaps_existing: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM flares
+                 WHERE start_date <= ?2 AND COALESCE(end_date, '9999-12-31') >= ?1",
+                params![start_date, end_date],
+                |r| r.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+
+        candidates.push(FlareCandidate {
+            flagged_days: window.len() as i64,
+            peak_burden: window.iter().map(|(_, b, _)| *b).max().unwrap_or(0),
+            mean_wellness: stats::mean(&window_wellness),
+            start_date,
+            end_date,
+            affected_systems,
+            overlaps_existing,
+        });
+    }
+
+    Ok(FlareSuggestions {
+        start_date: range.start_date.clone(),
+        end_date: range.end_date.clone(),
+        burden_threshold,
+        wellness_threshold,
+        candidates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use std::path::Path;
+
+    fn flare(start_date: &str, end_date: Option<&str>, severity: i64, systems: &[&str]) -> Flare {
+        Flare {
+            id: None,
+            start_date: start_date.to_string(),
+            end_date: end_date.map(str::to_string),
+            severity,
+            affected_systems: systems.iter().map(|s| s.to_string()).collect(),
+            triggers: String::new(),
+            treatment_notes: String::new(),
+        }
+    }
+
+    fn january() -> DateRange {
+        DateRange { start_date: "2024-01-01".to_string(), end_date: "2024-01-31".to_string() }
+    }
+
+    #[test]
+    fn saves_validate_and_replace_systems() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        for bad in [
+            flare("2024-02-30", None, 5, &[]),
+            flare("2024-03-01", Some("soon"), 5, &[]),
+            flare("2024-03-10", Some("2024-03-01"), 5, &[]),
+            flare("2024-03-01", None, MAX_SEVERITY + 1, &[]),
+            flare("2024-03-01", None, -1, &[]),
+            flare("2024-03-01", None, 5, &["Kidney"]),
+        ] {
+            assert!(save_flare(&conn, &bad).is_err(), "{:?}", bad);
+        }
+        let count = |conn: &Connection| -> i64 { conn.query_row("SELECT COUNT(*) FROM flares", [], |r| r.get(0)).unwrap() };
+        assert_eq!(count(&conn), 0);
+
+        let id = save_flare(&conn, &flare("2024-03-01", None, 5, &["Skin", "General"])).unwrap();
+        let update = Flare { id: Some(id), ..flare("2024-03-01", Some("2024-03-09"), 7, &["Musculoskeletal"]) };
+        assert_eq!(save_flare(&conn, &update).unwrap(), id);
+        let saved = query_flares(&conn, "2024-03-01", "2024-03-31").unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].end_date.as_deref(), Some("2024-03-09"));
+        assert_eq!(saved[0].affected_systems, ["Musculoskeletal"]);
+    }
+
+    #[test]
+    fn open_flares_overlap_everything_after_their_start() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        save_flare(&conn, &flare("2024-01-10", Some("2024-01-20"), 5, &[])).unwrap();
+        save_flare(&conn, &flare("2024-02-10", None, 5, &[])).unwrap();
+        let starts = |start: &str, end: &str| -> Vec<String> {
+            query_flares(&conn, start, end).unwrap().into_iter().map(|f| f.start_date).collect()
+        };
+        assert_eq!(starts("2024-01-20", "2024-01-31"), ["2024-01-10"]);
+        assert!(starts("2024-01-21", "2024-02-09").is_empty());
+        assert_eq!(starts("2025-06-01", "2025-06-30"), ["2024-02-10"]);
+    }
+
+    /// Ten logged days with Fatigue at 1. Joint Pain adds 6 on Jan 4, 5 and 7,
+    /// and wellness drops from 6 to 2 on Jan 10.
+    fn seed_days(conn: &Connection) {
+        for day in 1..=10 {
+            let date = format!("2024-01-{:02}", day);
+            conn.execute("INSERT INTO symptom_logs (log_date, symptom_id, severity) VALUES (?1, 1, 1)", [&date])
+                .unwrap();
+            if [4, 5, 7].contains(&day) {
+                conn.execute("INSERT INTO symptom_logs (log_date, symptom_id, severity) VALUES (?1, 2, 6)", [&date])
+                    .unwrap();
+            }
+            let wellness = if day == 10 { 2 } else { 6 };
+            conn.execute(
+                "INSERT INTO daily_summaries (log_date, wellness_score) VALUES (?1, ?2)",
+                params![date, wellness],
+            )
+            .unwrap();
+        }
+    }
+
+    fn windows(suggestions: &FlareSuggestions) -> Vec<(&str, &str)> {
+        suggestions.candidates.iter().map(|c| (c.start_date.as_str(), c.end_date.as_str())).collect()
+    }
+
+    #[test]
+    fn suggestions_flag_days_against_the_medians() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        seed_days(&conn);
+
+        let suggestions = suggest_flares(&conn, &january(), 3, 2).unwrap();
+        assert_eq!((suggestions.start_date.as_str(), suggestions.end_date.as_str()), ("2024-01-01", "2024-01-31"));
+        assert_eq!(suggestions.burden_threshold, 2.0);
+        assert_eq!(suggestions.wellness_threshold, Some(4.0));
+        // Jan 7 to Jan 10 is a two-day gap, so the wellness dip joins the window
+        assert_eq!(windows(&suggestions), [("2024-01-04", "2024-01-10")]);
+        let candidate = &suggestions.candidates[0];
+        assert_eq!((candidate.flagged_days, candidate.peak_burden), (4, 7));
+        assert_eq!(candidate.affected_systems, ["General", "Musculoskeletal"]);
+        assert!(!candidate.overlaps_existing);
+    }
+
+    #[test]
+    fn suggestions_split_on_gaps_and_drop_short_windows() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        seed_days(&conn);
+
+        let suggestions = suggest_flares(&conn, &january(), 1, 1).unwrap();
+        assert_eq!(windows(&suggestions), [("2024-01-04", "2024-01-07"), ("2024-01-10", "2024-01-10")]);
+        let suggestions = suggest_flares(&conn, &january(), 2, 0).unwrap();
+        assert_eq!(windows(&suggestions), [("2024-01-04", "2024-01-05")]);
+        assert!(suggest_flares(&conn, &january(), 3, 0).unwrap().candidates.is_empty());
+
+        save_flare(&conn, &flare("2024-01-06", None, 5, &[])).unwrap();
+        let suggestions = suggest_flares(&conn, &january(), 1, 1).unwrap();
+        let overlaps: Vec<bool> = suggestions.candidates.iter().map(|c| c.overlaps_existing).collect();
+        assert_eq!(overlaps, [true, true]);
+
+        assert!(suggest_flares(&conn, &january(), 0, 2).is_err());
+        assert!(suggest_flares(&conn, &january(), 3, -1).is_err());
+    }
+}
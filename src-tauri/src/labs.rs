@@ -0,0 +1,95 @@
+// Lab sessions and their results. Saving validates the draw date and test
+// names and records the writer's `source` ("app" or "mcp") on the session.
+
+use crate::date_range;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LabSession {
+    pub id: Option<i64>,
+    pub test_date: String,
+    pub lab_name: String,
+    pub notes: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LabResult {
+    pub id: Option<i64>,
+    pub session_id: Option<i64>,
+    pub test_name: String,
+    pub panel: String,
+    pub value: Option<f64>,
+    pub text_value: String,
+    pub unit: String,
+    pub ref_range_low: Option<f64>,
+    pub ref_range_high: Option<f64>,
+    pub flag: String,
+}
+
+/// Flag for a numeric result against its reference range, mirroring the lab
+/// entry form: "H"/"L" outside the range, "HH"/"LL" when further out than the
+/// width of the range, otherwise "N".
+pub fn auto_flag(value: Option<f64>, low: Option<f64>, high: Option<f64>) -> &'static str {
+    let Some(value) = value else { return "N" };
+    match (low, high) {
+        (Some(lo), Some(hi)) if value > hi + (hi - lo) => "HH",
+        (_, Some(hi)) if value > hi => "H",
+        (Some(lo), Some(hi)) if value < lo - (hi - lo) => "LL",
+        (Some(lo), _) if value < lo => "L",
+        _ => "N",
+    }
+}
+
+/// Inserts or updates a session and replaces its results. Results with
+/// neither a value nor a text value are skipped.
+pub fn save_session(conn: &Connection, session: &LabSession, results: &[LabResult], source: &str) -> Result<i64, String> {
+    date_range::parse_date(&session.test_date)?;
+    for r in results.iter().filter(|r| r.value.is_some() || !r.text_value.is_empty()) {
+        if r.test_name.trim().is_empty() {
+            return Err("Test name must not be empty".to_string());
+        }
+    }
+
+    let session_id = if let Some(id) = session.id {
+        conn.execute(
+            "UPDATE lab_sessions SET test_date = ?1, lab_name = ?2, notes = ?3, source = ?4 WHERE id = ?5",
+            params![session.test_date, session.lab_name, session.notes, source, id],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM lab_results WHERE session_id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+        id
+    } else {
+        conn.execute(
+            "INSERT INTO lab_sessions (test_date, lab_name, notes, source) VALUES (?1, ?2, ?3, ?4)",
+            params![session.test_date, session.lab_name, session.notes, source],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.last_insert_rowid()
+    };
+
+    for r in results {
+        if r.value.is_none() && r.text_value.is_empty() {
+            continue;
+        }
+        conn.execute(
+            "INSERT INTO lab_results (session_id, test_name, panel, value, text_value, unit, ref_range_low, ref_range_high, flag)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                session_id,
+                r.test_name,
+                r.panel,
+                r.value,
+                r.text_value,
+                r.unit,
+                r.ref_range_low,
+                r.ref_range_high,
+                r.flag,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(session_id)
+}
@@ -1,5 +1,20 @@
+pub mod analysis;
+pub mod anticoagulation;
+mod biological_variation;
 mod commands;
-mod db;
+pub mod date_range;
+pub mod db;
+pub mod flares;
+pub mod labs;
+pub mod mcp_access_log;
+pub mod mcp_policy;
+pub mod mcp_writes;
+pub mod rollups;
+pub mod search;
+mod stats;
+pub mod symptoms;
+pub mod tags;
+pub mod vitals;
 
 use db::Database;
 
@@ -23,9 +38,11 @@ pub fn run() {
             commands::labs::get_latest_abnormal,
             commands::labs::get_latest_abnormal_with_previous,
             commands::labs::get_trends,
+            commands::labs::get_trends_with_medications,
             commands::labs::get_all_test_names,
             commands::symptoms::get_wellness_trends,
             commands::symptoms::get_symptom_trends,
+            commands::symptoms::get_all_symptom_trends,
             commands::symptoms::get_active_symptom_names,
             commands::symptoms::get_symptoms,
             commands::symptoms::save_symptom,
@@ -33,9 +50,56 @@ pub fn run() {
             commands::symptoms::reorder_symptoms,
             commands::symptoms::get_symptom_log,
             commands::symptoms::save_symptom_log,
+            commands::symptoms::get_symptom_intraday,
+            commands::symptoms::get_body_locations,
+            commands::symptoms::get_body_location_summary,
+            commands::medications::get_medication_events,
+            commands::medications::save_medication_event,
+            commands::medications::delete_medication_event,
+            commands::medications::get_medication_effect,
+            commands::anticoagulation::get_inr_therapeutic_range,
+            commands::anticoagulation::save_inr_therapeutic_range,
+            commands::anticoagulation::get_warfarin_doses,
+            commands::anticoagulation::save_warfarin_dose,
+            commands::anticoagulation::delete_warfarin_dose,
+            commands::anticoagulation::get_inr_report,
+            commands::vitals::get_vitals,
+            commands::vitals::save_vital_reading,
+            commands::vitals::delete_vital_reading,
+            commands::vitals::get_vital_ranges,
+            commands::vitals::save_vital_range,
+            commands::vitals::get_vital_trends,
+            commands::flares::get_flares,
+            commands::flares::save_flare,
+            commands::flares::delete_flare,
+            commands::flares::suggest_flares,
+            commands::analysis::get_lab_symptom_correlation,
+            commands::analysis::get_symptom_cross_correlation,
+            commands::analysis::get_test_summary,
+            commands::calendar::get_calendar_heatmap,
+            commands::rollups::get_rollups,
+            commands::search::search_notes,
+            commands::tags::get_tags,
+            commands::tags::save_tag,
+            commands::tags::delete_tag,
+            commands::tags::get_day_tags,
+            commands::tags::set_day_tags,
+            commands::tags::get_lab_session_tags,
+            commands::tags::set_lab_session_tags,
+            commands::tags::get_symptom_log_tags,
+            commands::tags::set_symptom_log_tags,
+            commands::tags::get_tag_comparison,
+            commands::mcp_writes::get_mcp_writes,
+            commands::mcp_writes::undo_mcp_write,
+            commands::mcp_policy::get_mcp_policy,
+            commands::mcp_policy::save_mcp_policy,
+            commands::mcp_policy::get_mcp_policy_options,
+            commands::mcp_access_log::get_mcp_access_log,
+            commands::mcp_access_log::clear_mcp_access_log,
             commands::export::export_data,
             commands::settings::get_setting,
             commands::settings::set_setting,
+            commands::settings::regenerate_mcp_http_token,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,202 @@
+// Record of what MCP clients read or wrote through the MCP server. The server
+// opens the tracker database read-only, so it appends to a sidecar database
+// next to it instead; the app lists the entries in Settings, and both prune
+// them according to the `mcp_access_log_days` setting.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const FILE_NAME: &str = "mcp_access.db";
+pub const DEFAULT_RETENTION_DAYS: i64 = 90;
+
+pub const KIND_TOOL: &str = "tool";
+pub const KIND_RESOURCE: &str = "resource";
+pub const KIND_PROMPT: &str = "prompt";
+
+#[derive(Debug, Serialize)]
+pub struct McpAccess {
+    pub id: i64,
+    pub accessed_at: String,
+    pub client: String,
+    /// "tool", "resource" or "prompt".
+    pub kind: String,
+    /// Tool or prompt name, or resource URI.
+    pub name: String,
+    /// Arguments as JSON, empty when there were none.
+    pub params: String,
+    /// Records returned, when the response is a list.
+    pub row_count: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// The sidecar log for the tracker database at `db_path`.
+pub fn path_for(db_path: &Path) -> PathBuf {
+    db_path.with_file_name(FILE_NAME)
+}
+
+/// Opens the log, creating it on first use. Both the app and the MCP server
+/// write to it, so waits briefly for the other's writes.
+pub fn open(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    conn.busy_timeout(Duration::from_secs(5)).map_err(|e| e.to_string())?;
+    conn.execute_batch(
+        "
+        PRAGMA journal_mode=WAL;
+        CREATE TABLE IF NOT EXISTS mcp_access (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            accessed_at TEXT NOT NULL DEFAULT (datetime('now')),
+            client TEXT NOT NULL DEFAULT '',
+            kind TEXT NOT NULL,
+            name TEXT NOT NULL,
+            params TEXT NOT NULL DEFAULT '',
+            row_count INTEGER,
+            error TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_mcp_access_time ON mcp_access(accessed_at);
+        ",
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+pub struct NewAccess<'a> {
+    pub client: &'a str,
+    pub kind: &'a str,
+    pub name: &'a str,
+    pub params: &'a str,
+    pub row_count: Option<i64>,
+    pub error: Option<&'a str>,
+}
+
+pub fn record(conn: &Connection, access: &NewAccess) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO mcp_access (client, kind, name, params, row_count, error) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![access.client, access.kind, access.name, access.params, access.row_count, access.error],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Most recent first.
+pub fn list(conn: &Connection, limit: i64) -> Result<Vec<McpAccess>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, accessed_at, client, kind, name, params, row_count, error
+             FROM mcp_access ORDER BY id DESC LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![limit], |row| {
+            Ok(McpAccess {
+                id: row.get(0)?,
+                accessed_at: row.get(1)?,
+                client: row.get(2)?,
+                kind: row.get(3)?,
+                name: row.get(4)?,
+                params: row.get(5)?,
+                row_count: row.get(6)?,
+                error: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// The retention setting from the tracker database; 0 keeps everything.
+pub fn retention_days(tracker: &Connection) -> i64 {
+    tracker
+        .query_row("SELECT value FROM settings WHERE key = 'mcp_access_log_days'", [], |r| r.get::<_, String>(0))
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_RETENTION_DAYS)
+}
+
+/// Deletes entries older than `retention_days`; 0 keeps everything.
+pub fn prune(conn: &Connection, retention_days: i64) -> Result<(), String> {
+    if retention_days <= 0 {
+        return Ok(());
+    }
+    conn.execute(
+        "DELETE FROM mcp_access WHERE accessed_at < datetime('now', '-' || ?1 || ' days')",
+        params![retention_days],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn clear(conn: &Connection) -> Result<(), String> {
+    conn.execute("DELETE FROM mcp_access", []).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    fn access<'a>(name: &'a str, error: Option<&'a str>) -> NewAccess<'a> {
+        NewAccess { client: "claude", kind: KIND_TOOL, name, params: "", row_count: Some(2), error }
+    }
+
+    fn names(conn: &Connection) -> Vec<String> {
+        list(conn, 10).unwrap().into_iter().map(|entry| entry.name).collect()
+    }
+
+    #[test]
+    fn entries_are_listed_newest_first() {
+        let conn = open(Path::new(":memory:")).unwrap();
+        record(&conn, &access("get_recent_labs", None)).unwrap();
+        record(&conn, &access("log_symptoms", Some("MCP writes are disabled"))).unwrap();
+
+        let entries = list(&conn, 10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "log_symptoms");
+        assert_eq!(entries[0].error.as_deref(), Some("MCP writes are disabled"));
+        assert_eq!((entries[1].client.as_str(), entries[1].kind.as_str()), ("claude", KIND_TOOL));
+        assert_eq!((entries[1].row_count, entries[1].error.as_deref()), (Some(2), None));
+        assert_eq!(list(&conn, 1).unwrap().len(), 1);
+
+        clear(&conn).unwrap();
+        assert!(names(&conn).is_empty());
+    }
+
+    #[test]
+    fn pruning_drops_entries_past_the_retention() {
+        let conn = open(Path::new(":memory:")).unwrap();
+        record(&conn, &access("recent", None)).unwrap();
+        for (name, days) in [("old", 40), ("older", 100)] {
+            conn.execute(
+                "INSERT INTO mcp_access (accessed_at, kind, name) VALUES (datetime('now', ?1), 'tool', ?2)",
+                params![format!("-{} days", days), name],
+            )
+            .unwrap();
+        }
+
+        prune(&conn, 0).unwrap();
+        assert_eq!(names(&conn).len(), 3);
+        prune(&conn, 90).unwrap();
+        assert_eq!(names(&conn), ["old", "recent"]);
+        prune(&conn, 30).unwrap();
+        assert_eq!(names(&conn), ["recent"]);
+    }
+
+    #[test]
+    fn retention_comes_from_settings() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        let set = |value: &str| {
+            conn.execute("UPDATE settings SET value = ?1 WHERE key = 'mcp_access_log_days'", params![value]).unwrap();
+        };
+        assert_eq!(retention_days(&conn), DEFAULT_RETENTION_DAYS);
+        set("30");
+        assert_eq!(retention_days(&conn), 30);
+        set("0");
+        assert_eq!(retention_days(&conn), 0);
+        set("forever");
+        assert_eq!(retention_days(&conn), DEFAULT_RETENTION_DAYS);
+        conn.execute("DELETE FROM settings WHERE key = 'mcp_access_log_days'", []).unwrap();
+        assert_eq!(retention_days(&conn), DEFAULT_RETENTION_DAYS);
+    }
+}
@@ -0,0 +1,333 @@
+// Fine-grained limits on what MCP clients may see, on top of the
+// `mcp_enabled` switch. The policy is stored as JSON in the `mcp_policy`
+// setting, edited in the app's Settings and enforced by the MCP server: tools
+// it blocks are neither advertised nor callable, and every read connection
+// the server opens gets temporary views that shadow the tracker tables with
+// the filtered, redacted rows, so all tools, resources and prompts see the
+// same restricted data. Write tools go through the tables directly, so they
+// check hidden panels, hidden symptom categories and the look-back limit
+// themselves.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Deserializer, Serialize};
+
+pub const CATEGORY_LABS: &str = "labs";
+pub const CATEGORY_SYMPTOMS: &str = "symptoms";
+pub const CATEGORY_VITALS: &str = "vitals";
+pub const CATEGORY_MEDICATIONS: &str = "medications";
+pub const CATEGORY_FLARES: &str = "flares";
+/// Free-text note search. Hits only come from notes that are neither hidden
+/// nor redacted.
+pub const CATEGORY_NOTES: &str = "notes";
+
+pub const CATEGORIES: &[&str] = &[
+    CATEGORY_LABS,
+    CATEGORY_SYMPTOMS,
+    CATEGORY_VITALS,
+    CATEGORY_MEDICATIONS,
+    CATEGORY_FLARES,
+    CATEGORY_NOTES,
+];
+
+/// Categories whose records carry free-text notes that can be redacted.
+pub const NOTE_CATEGORIES: &[&str] = &[
+    CATEGORY_LABS,
+    CATEGORY_SYMPTOMS,
+    CATEGORY_VITALS,
+    CATEGORY_MEDICATIONS,
+    CATEGORY_FLARES,
+];
+
+const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Serialize)]
+pub struct McpToolInfo {
+    pub name: &'static str,
+    /// Every category the tool reads or writes; it is available only while
+    /// all of them are allowed.
+    pub categories: &'static [&'static str],
+    pub label: &'static str,
+    pub writes: bool,
+}
+
+const fn tool(
+    name: &'static str,
+    categories: &'static [&'static str],
+    label: &'static str,
+    writes: bool,
+) -> McpToolInfo {
+    McpToolInfo { name, categories, label, writes }
+}
+
+/// Every tool the MCP server offers.
+pub const TOOLS: &[McpToolInfo] = &[
+    tool("list_test_names", &[CATEGORY_LABS], "List of lab tests", false),
+    tool("list_custom_tests", &[CATEGORY_LABS], "Custom test definitions", false),
+    tool("list_symptoms", &[CATEGORY_SYMPTOMS], "List of tracked symptoms", false),
+    tool("get_recent_labs", &[CATEGORY_LABS], "Recent lab results", false),
+    tool("get_abnormal_labs", &[CATEGORY_LABS], "Abnormal lab values", false),
+    tool("get_trends", &[CATEGORY_LABS], "Test trends over time", false),
+    tool("get_symptom_history", &[CATEGORY_SYMPTOMS], "Symptom history", false),
+    tool("get_daily_summaries", &[CATEGORY_SYMPTOMS], "Daily wellness summaries", false),
+    tool("get_rollups", &[CATEGORY_SYMPTOMS], "Weekly and monthly rollups", false),
+    tool("get_vitals", &[CATEGORY_VITALS], "Vital signs", false),
+    tool("get_inr_report", &[CATEGORY_MEDICATIONS], "INR report and warfarin doses", false),
+    tool("get_flares", &[CATEGORY_FLARES], "Flare episodes", false),
+    tool(
+        "get_lab_symptom_correlation",
+        &[CATEGORY_LABS, CATEGORY_SYMPTOMS],
+        "Lab–symptom correlation",
+        false,
+    ),
+    tool("search_notes", &[CATEGORY_NOTES], "Full-text note search", false),
+    tool("log_symptoms", &[CATEGORY_SYMPTOMS], "Log symptoms", true),
+    tool("add_lab_session", &[CATEGORY_LABS], "Add lab sessions", true),
+    tool("add_daily_note", &[CATEGORY_SYMPTOMS], "Add daily notes", true),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct McpPolicy {
+    pub blocked_categories: Vec<String>,
+    pub blocked_tools: Vec<String>,
+    /// Lab panels whose results are hidden, matched case-insensitively.
+    pub hidden_panels: Vec<String>,
+    /// Symptom categories (e.g. "Mental health") whose symptoms and logs are
+    /// hidden, matched case-insensitively.
+    pub hidden_symptom_categories: Vec<String>,
+    /// Only records from the last N days are visible.
+    pub max_days: Option<i64>,
+    /// Categories whose free-text notes read as "[redacted]", e.g. symptom
+    /// notes while lab notes stay readable. Policies saved before this was
+    /// per category stored `redact_notes: true`, which reads as all of them.
+    #[serde(alias = "redact_notes", deserialize_with = "redacted_categories")]
+    pub redact_note_categories: Vec<String>,
+}
+
+fn redacted_categories<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Redaction {
+        All(bool),
+        Categories(Vec<String>),
+    }
+    Ok(match Redaction::deserialize(deserializer)? {
+        Redaction::All(true) => NOTE_CATEGORIES.iter().map(|c| c.to_string()).collect(),
+        Redaction::All(false) => Vec::new(),
+        Redaction::Categories(categories) => categories,
+    })
+}
+
+impl McpPolicy {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let json: Option<String> = conn
+            .query_row("SELECT value FROM settings WHERE key = 'mcp_policy'", [], |r| r.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        match json {
+            Some(json) if !json.is_empty() => {
+                serde_json::from_str(&json).map_err(|e| format!("Invalid MCP access policy: {}", e))
+            }
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self, conn: &Connection) -> Result<(), String> {
+        if self.max_days.is_some_and(|days| days < 1) {
+            return Err("The look-back limit must be at least 1 day".to_string());
+        }
+        if let Some(category) = self.blocked_categories.iter().find(|c| !CATEGORIES.contains(&c.as_str())) {
+            return Err(format!("Unknown MCP data category: {}", category));
+        }
+        if let Some(category) = self.redact_note_categories.iter().find(|c| !NOTE_CATEGORIES.contains(&c.as_str())) {
+            return Err(format!("Notes can't be redacted for: {}", category));
+        }
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES ('mcp_policy', ?1)",
+            [json],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    pub fn allows_category(&self, category: &str) -> bool {
+        !self.blocked_categories.iter().any(|c| c == category)
+    }
+
+    pub fn redacts_notes(&self, category: &str) -> bool {
+        self.redact_note_categories.iter().any(|c| c == category)
+    }
+
+    /// Tools missing from `TOOLS` are governed by `blocked_tools` alone.
+    pub fn allows_tool(&self, name: &str) -> bool {
+        if self.blocked_tools.iter().any(|t| t == name) {
+            return false;
+        }
+        TOOLS
+            .iter()
+            .find(|t| t.name == name)
+            .is_none_or(|t| t.categories.iter().all(|c| self.allows_category(c)))
+    }
+
+    pub fn hides_panel(&self, panel: &str) -> bool {
+        self.hidden_panels.iter().any(|p| p.eq_ignore_ascii_case(panel))
+    }
+
+    pub fn hides_symptom_category(&self, category: &str) -> bool {
+        self.hidden_symptom_categories.iter().any(|c| c.eq_ignore_ascii_case(category))
+    }
+
+    /// Earliest visible date as a SQL expression, if look-back is capped.
+    fn cutoff_sql(&self) -> Option<String> {
+        self.max_days.map(|days| format!("date('now', '-{} days')", days.max(1)))
+    }
+
+    /// Earliest visible date, if look-back is capped.
+    pub fn cutoff_date(&self, conn: &Connection) -> Result<Option<String>, String> {
+        match self.cutoff_sql() {
+            Some(sql) => conn
+                .query_row(&format!("SELECT {}", sql), [], |r| r.get(0))
+                .map(Some)
+                .map_err(|e| e.to_string()),
+            None => Ok(None),
+        }
+    }
+}
+
+/// How one table is filtered for MCP reads.
+struct ViewSpec {
+    table: &'static str,
+    category: Option<&'static str>,
+    date_column: Option<&'static str>,
+    /// Restricts rows to parents that are themselves visible. Unqualified
+    /// table names here resolve to the views created before this one.
+    parent_filter: Option<&'static str>,
+    free_text: &'static [&'static str],
+}
+
+const fn view(
+    table: &'static str,
+    category: Option<&'static str>,
+    date_column: Option<&'static str>,
+    parent_filter: Option<&'static str>,
+    free_text: &'static [&'static str],
+) -> ViewSpec {
+    ViewSpec { table, category, date_column, parent_filter, free_text }
+}
+
+// Parents come before the tables that filter on them.
+const VIEWS: &[ViewSpec] = &[
+    view("lab_sessions", Some(CATEGORY_LABS), Some("test_date"), None, &["notes"]),
+    view("lab_results", Some(CATEGORY_LABS), None, Some("session_id IN (SELECT id FROM lab_sessions)"), &[]),
+    view("lab_session_tags", None, None, Some("session_id IN (SELECT id FROM lab_sessions)"), &[]),
+    view("custom_lab_tests", Some(CATEGORY_LABS), None, None, &[]),
+    view("symptoms", Some(CATEGORY_SYMPTOMS), None, None, &[]),
+    view("symptom_logs", Some(CATEGORY_SYMPTOMS), Some("log_date"), Some("symptom_id IN (SELECT id FROM symptoms)"), &["notes"]),
+    view("symptom_log_entries", Some(CATEGORY_SYMPTOMS), Some("log_date"), Some("symptom_id IN (SELECT id FROM symptoms)"), &["notes"]),
+    view("symptom_log_locations", Some(CATEGORY_SYMPTOMS), Some("log_date"), Some("symptom_id IN (SELECT id FROM symptoms)"), &[]),
+    view("symptom_log_tags", Some(CATEGORY_SYMPTOMS), Some("log_date"), Some("symptom_id IN (SELECT id FROM symptoms)"), &[]),
+    view("daily_summaries", Some(CATEGORY_SYMPTOMS), Some("log_date"), None, &["notes"]),
+    view("day_tags", None, Some("log_date"), None, &[]),
+    view("vital_readings", Some(CATEGORY_VITALS), Some("measured_at"), None, &["notes"]),
+    view("medication_events", Some(CATEGORY_MEDICATIONS), Some("event_date"), None, &["notes"]),
+    view("warfarin_doses", Some(CATEGORY_MEDICATIONS), Some("week_start"), None, &["notes"]),
+    view("flares", Some(CATEGORY_FLARES), Some("start_date"), None, &["triggers", "treatment_notes"]),
+    view("flare_systems", Some(CATEGORY_FLARES), None, Some("flare_id IN (SELECT id FROM flares)"), &[]),
+];
+
+fn sql_list(values: &[String]) -> String {
+    values
+        .iter()
+        .map(|v| format!("'{}'", v.to_lowercase().replace('\'', "''")))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Shadows the tracker tables on `conn` with TEMP views restricted by
+/// `policy`. Unqualified queries resolve to the temp schema first, so the
+/// shared query code needs no changes. Only for read-only connections.
+pub fn apply_read_views(conn: &Connection, policy: &McpPolicy) -> Result<(), String> {
+    if *policy == McpPolicy::default() {
+        return Ok(());
+    }
+    let cutoff = policy.cutoff_sql();
+
+    for spec in VIEWS {
+        let columns: Vec<String> = conn
+            .prepare(&format!("PRAGMA main.table_info({})", spec.table))
+            .and_then(|mut stmt| stmt.query_map([], |r| r.get(1))?.collect())
+            .map_err(|e| e.to_string())?;
+        // Table from a newer schema than this database
+        if columns.is_empty() {
+            continue;
+        }
+
+        let redact = spec.category.is_some_and(|c| policy.redacts_notes(c));
+        let select = columns
+            .iter()
+            .map(|c| {
+                if redact && spec.free_text.contains(&c.as_str()) {
+                    format!("CASE WHEN {0} = '' THEN {0} ELSE '{1}' END AS {0}", c, REDACTED)
+                } else {
+                    c.clone()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut conditions = Vec::new();
+        if spec.category.is_some_and(|c| !policy.allows_category(c)) {
+            conditions.push("0".to_string());
+        }
+        if let (Some(column), Some(cutoff)) = (spec.date_column, &cutoff) {
+            conditions.push(format!("{} >= {}", column, cutoff));
+        }
+        if let Some(filter) = spec.parent_filter {
+            conditions.push(filter.to_string());
+        }
+        match spec.table {
+            "lab_results" | "custom_lab_tests" if !policy.hidden_panels.is_empty() => {
+                conditions.push(format!("lower(panel) NOT IN ({})", sql_list(&policy.hidden_panels)));
+            }
+            "symptoms" if !policy.hidden_symptom_categories.is_empty() => {
+                conditions.push(format!(
+                    "lower(category) NOT IN ({})",
+                    sql_list(&policy.hidden_symptom_categories)
+                ));
+            }
+            _ => {}
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+        conn.execute_batch(&format!(
+            "CREATE TEMP VIEW IF NOT EXISTS {0} AS SELECT {1} FROM main.{0}{2};",
+            spec.table, select, where_clause
+        ))
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> McpPolicy {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn redaction_reads_old_and_per_category_policies() {
+        assert_eq!(parse(r#"{"redact_notes": true}"#).redact_note_categories, NOTE_CATEGORIES);
+        assert!(parse(r#"{"redact_notes": false}"#).redact_note_categories.is_empty());
+        let policy = parse(r#"{"redact_note_categories": ["symptoms"]}"#);
+        assert!(policy.redacts_notes(CATEGORY_SYMPTOMS));
+        assert!(!policy.redacts_notes(CATEGORY_LABS));
+        assert!(parse("{}").redact_note_categories.is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+// Journal of changes made through the MCP server's write tools. The app lists
+// them for review and can undo each one: a day write restores the day's log
+// as it was before, a lab session write deletes the session. Undo refuses
+// when the record has since been edited in the app, or when a later MCP write
+// to the same day is still in place.
+
+use crate::symptoms::{self, DaySnapshot};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+pub const SOURCE_APP: &str = "app";
+pub const SOURCE_MCP: &str = "mcp";
+
+#[derive(Debug, Serialize)]
+pub struct McpWrite {
+    pub id: i64,
+    pub tool: String,
+    pub summary: String,
+    pub record_date: String,
+    pub session_id: Option<i64>,
+    pub created_at: String,
+    pub undone_at: Option<String>,
+}
+
+/// What an MCP write changed, and what undoing it needs.
+pub enum WriteTarget {
+    /// A day's symptom log or summary, with the log before the write (`None`
+    /// if nothing was logged that day).
+    Day { previous: Option<DaySnapshot> },
+    /// A lab session created by the write.
+    LabSession(i64),
+}
+
+pub fn record(
+    conn: &Connection,
+    tool: &str,
+    summary: &str,
+    record_date: &str,
+    target: &WriteTarget,
+) -> Result<i64, String> {
+    let (session_id, previous_day) = match target {
+        WriteTarget::Day { previous } => {
+            let json = previous
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()
+                .map_err(|e| e.to_string())?;
+            (None, json)
+        }
+        WriteTarget::LabSession(id) => (Some(*id), None),
+    };
+    conn.execute(
+        "INSERT INTO mcp_writes (tool, summary, record_date, session_id, previous_day) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![tool, summary, record_date, session_id, previous_day],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Most recent writes first.
+pub fn list(conn: &Connection, limit: i64) -> Result<Vec<McpWrite>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, tool, summary, record_date, session_id, created_at, undone_at
+             FROM mcp_writes ORDER BY id DESC LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![limit], |row| {
+            Ok(McpWrite {
+                id: row.get(0)?,
+                tool: row.get(1)?,
+                summary: row.get(2)?,
+                record_date: row.get(3)?,
+                session_id: row.get(4)?,
+                created_at: row.get(5)?,
+                undone_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn undo(conn: &Connection, id: i64) -> Result<(), String> {
+    let (record_date, session_id, previous_day, undone_at): (String, Option<i64>, Option<String>, Option<String>) = conn
+        .query_row(
+            "SELECT record_date, session_id, previous_day, undone_at FROM mcp_writes WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("MCP change {} not found", id),
+            e => e.to_string(),
+        })?;
+    if undone_at.is_some() {
+        return Err("This change has already been undone".to_string());
+    }
+
+    if let Some(session_id) = session_id {
+        let source: Option<String> = conn
+            .query_row("SELECT source FROM lab_sessions WHERE id = ?1", params![session_id], |row| row.get(0))
+            .ok();
+        match source.as_deref() {
+            // Already deleted in the app
+            None => {}
+            Some(SOURCE_MCP) => {
+                conn.execute("DELETE FROM lab_sessions WHERE id = ?1", params![session_id])
+                    .map_err(|e| e.to_string())?;
+            }
+            Some(_) => {
+                return Err("This lab session was edited in the app after the MCP change, so it can't be undone".to_string());
+            }
+        }
+    } else {
+        let later_write: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM mcp_writes
+                   WHERE record_date = ?1 AND session_id IS NULL AND id > ?2 AND undone_at IS NULL)",
+                params![record_date, id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if later_write {
+            return Err(format!("Undo the later MCP change to {} first", record_date));
+        }
+        if symptoms::day_snapshot(conn, &record_date)?.is_some_and(|day| day.source != SOURCE_MCP) {
+            return Err(format!(
+                "The log for {} was edited in the app after the MCP change, so it can't be undone",
+                record_date
+            ));
+        }
+        match previous_day {
+            Some(json) => {
+                let day: DaySnapshot = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+                symptoms::save_day(conn, &record_date, &day.entries, day.wellness, &day.notes, &day.source)?;
+                if !day.has_summary {
+                    conn.execute("DELETE FROM daily_summaries WHERE log_date = ?1", params![record_date])
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+            None => symptoms::delete_day(conn, &record_date)?,
+        }
+    }
+
+    conn.execute("UPDATE mcp_writes SET undone_at = datetime('now') WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use std::path::Path;
+
+    #[test]
+    fn undo_restores_a_day_without_inventing_its_summary() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        let date = "2024-03-01";
+        conn.execute(
+            "INSERT INTO symptom_logs (log_date, symptom_id, severity, notes, source) VALUES (?1, 1, 2, '', 'mcp')",
+            params![date],
+        )
+        .unwrap();
+
+        let previous = symptoms::day_snapshot(&conn, date).unwrap();
+        assert!(previous.as_ref().is_some_and(|day| !day.has_summary));
+        let mut entries = previous.as_ref().unwrap().entries.clone();
+        entries[0].severity = 7;
+        entries[0].intraday = None;
+        symptoms::save_day(&conn, date, &entries, 4, "", SOURCE_MCP).unwrap();
+        let id = record(&conn, "log_symptoms", "test", date, &WriteTarget::Day { previous }).unwrap();
+
+        undo(&conn, id).unwrap();
+        let severity: i64 = conn
+            .query_row("SELECT severity FROM symptom_logs WHERE log_date = ?1 AND symptom_id = 1", params![date], |r| r.get(0))
+            .unwrap();
+        assert_eq!(severity, 2);
+        let summaries: i64 = conn
+            .query_row("SELECT COUNT(*) FROM daily_summaries WHERE log_date = ?1", params![date], |r| r.get(0))
+            .unwrap();
+        assert_eq!(summaries, 0);
+    }
+}
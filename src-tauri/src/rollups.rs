@@ -0,0 +1,279 @@
+// Weekly and monthly rollups of symptom logs and wellness scores, for long
+// histories where raw days are too noisy.
+
+use crate::date_range::{self, DateRange};
+use crate::stats;
+use chrono::{Datelike, Duration, NaiveDate};
+use rusqlite::{params, Connection};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RollupPeriod {
+    /// ISO 8601 week, Monday to Sunday.
+    Week,
+    Month,
+}
+
+impl RollupPeriod {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            _ => Err(format!("Invalid rollup period (expected week or month): {}", s)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Week => "week",
+            Self::Month => "month",
+        }
+    }
+
+    /// Bucket label and first/last calendar day of the bucket holding `day`.
+    fn bucket(&self, day: NaiveDate) -> (String, NaiveDate, NaiveDate) {
+        match self {
+            Self::Week => {
+                let week = day.iso_week();
+                let start = day - Duration::days(day.weekday().num_days_from_monday() as i64);
+                (format!("{}-W{:02}", week.year(), week.week()), start, start + Duration::days(6))
+            }
+            Self::Month => {
+                let start = day.with_day(1).unwrap();
+                let next = if start.month() == 12 {
+                    NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+                }
+                .unwrap();
+                (start.format("%Y-%m").to_string(), start, next - Duration::days(1))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct SymptomRollup {
+    pub symptom_id: i64,
+    pub name: String,
+    pub days_present: i64,
+    /// Share of the bucket's logged days on which the symptom was present.
+    pub frequency: f64,
+    /// Mean over logged days; a logged day without an entry counts as 0.
+    pub mean_severity: f64,
+    pub max_severity: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RollupBucket {
+    pub label: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub days_logged: i64,
+    pub mean_wellness: Option<f64>,
+    pub symptoms: Vec<SymptomRollup>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Rollup {
+    pub period: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub buckets: Vec<RollupBucket>,
+}
+
+struct BucketAcc {
+    start: NaiveDate,
+    end: NaiveDate,
+    days: HashSet<NaiveDate>,
+    wellness: Vec<f64>,
+    // symptom_id -> severities on the days it was logged
+    severities: HashMap<i64, Vec<i64>>,
+}
+
+fn bucket_for(buckets: &mut BTreeMap<String, BucketAcc>, period: RollupPeriod, day: NaiveDate) -> &mut BucketAcc {
+    let (label, start, end) = period.bucket(day);
+    let acc = buckets.entry(label).or_insert_with(|| BucketAcc {
+        start,
+        end,
+        days: HashSet::new(),
+        wellness: Vec::new(),
+        severities: HashMap::new(),
+    });
+    acc.days.insert(day);
+    acc
+}
+
+/// Buckets the logged days in `range` by `period`. Only buckets with at
+/// least one logged day are returned, and each lists only the symptoms that
+/// were recorded in it. Buckets at the edges cover just the part inside
+/// `range`.
+pub fn query_rollups(conn: &Connection, period: RollupPeriod, range: &DateRange) -> Result<Rollup, String> {
+    query_rollups_on(conn, period, range, None)
+}
+
+/// `query_rollups` restricted to the logged days in `only_dates`, e.g. the
+/// dates carrying a tag.
+pub fn query_rollups_on(
+    conn: &Connection,
+    period: RollupPeriod,
+    range: &DateRange,
+    only_dates: Option<&HashSet<String>>,
+) -> Result<Rollup, String> {
+    let keep = |date: &str| only_dates.is_none_or(|dates| dates.contains(date));
+    let mut stmt = conn
+        .prepare("SELECT log_date, wellness_score FROM daily_summaries WHERE log_date >= ?1 AND log_date <= ?2")
+        .map_err(|e| e.to_string())?;
+    let wellness: Vec<(String, i64)> = stmt
+        .query_map(params![range.start_date, range.end_date], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT log_date, symptom_id, severity FROM symptom_logs WHERE log_date >= ?1 AND log_date <= ?2")
+        .map_err(|e| e.to_string())?;
+    let logs: Vec<(String, i64, i64)> = stmt
+        .query_map(params![range.start_date, range.end_date], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn.prepare("SELECT id, name FROM symptoms").map_err(|e| e.to_string())?;
+    let names: HashMap<i64, String> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut buckets: BTreeMap<String, BucketAcc> = BTreeMap::new();
+    for (date, score) in wellness.iter().filter(|(date, _)| keep(date)) {
+        bucket_for(&mut buckets, period, date_range::parse_date(date)?)
+            .wellness
+            .push(*score as f64);
+    }
+    for (date, symptom_id, severity) in logs.iter().filter(|(date, _, _)| keep(date)) {
+        bucket_for(&mut buckets, period, date_range::parse_date(date)?)
+            .severities
+            .entry(*symptom_id)
+            .or_default()
+            .push(*severity);
+    }
+
+    let range_start = date_range::parse_date(&range.start_date)?;
+    let range_end = date_range::parse_date(&range.end_date)?;
+    let buckets = buckets
+        .into_iter()
+        .map(|(label, acc)| {
+            let days_logged = acc.days.len() as i64;
+            let mut symptoms: Vec<SymptomRollup> = acc
+                .severities
+                .iter()
+                .map(|(symptom_id, severities)| SymptomRollup {
+                    symptom_id: *symptom_id,
+                    name: names.get(symptom_id).cloned().unwrap_or_default(),
+                    days_present: severities.iter().filter(|s| **s > 0).count() as i64,
+                    frequency: severities.iter().filter(|s| **s > 0).count() as f64 / days_logged as f64,
+                    mean_severity: severities.iter().sum::<i64>() as f64 / days_logged as f64,
+                    max_severity: severities.iter().copied().max().unwrap_or(0),
+                })
+                .collect();
+            symptoms.sort_by(|a, b| b.days_present.cmp(&a.days_present).then_with(|| a.name.cmp(&b.name)));
+            RollupBucket {
+                label,
+                start_date: acc.start.max(range_start).format("%Y-%m-%d").to_string(),
+                end_date: acc.end.min(range_end).format("%Y-%m-%d").to_string(),
+                days_logged,
+                mean_wellness: stats::mean(&acc.wellness),
+                symptoms,
+            }
+        })
+        .collect();
+
+    Ok(Rollup {
+        period: period.as_str().to_string(),
+        start_date: range.start_date.clone(),
+        end_date: range.end_date.clone(),
+        buckets,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use std::path::Path;
+
+    fn day(s: &str) -> NaiveDate {
+        date_range::parse_date(s).unwrap()
+    }
+
+    fn bucket(period: RollupPeriod, s: &str) -> (String, String, String) {
+        let (label, start, end) = period.bucket(day(s));
+        (label, start.to_string(), end.to_string())
+    }
+
+    fn strings(label: &str, start: &str, end: &str) -> (String, String, String) {
+        (label.to_string(), start.to_string(), end.to_string())
+    }
+
+    #[test]
+    fn weeks_follow_iso_numbering() {
+        assert_eq!(bucket(RollupPeriod::Week, "2024-01-01"), strings("2024-W01", "2024-01-01", "2024-01-07"));
+        assert_eq!(bucket(RollupPeriod::Week, "2024-01-07"), strings("2024-W01", "2024-01-01", "2024-01-07"));
+        // A Sunday at the start of the year belongs to the previous ISO year
+        assert_eq!(bucket(RollupPeriod::Week, "2023-01-01"), strings("2022-W52", "2022-12-26", "2023-01-01"));
+        // And late December can fall in week 1 of the next
+        assert_eq!(bucket(RollupPeriod::Week, "2024-12-31"), strings("2025-W01", "2024-12-30", "2025-01-05"));
+    }
+
+    #[test]
+    fn months_run_to_their_last_day() {
+        assert_eq!(bucket(RollupPeriod::Month, "2024-02-15"), strings("2024-02", "2024-02-01", "2024-02-29"));
+        assert_eq!(bucket(RollupPeriod::Month, "2023-02-01"), strings("2023-02", "2023-02-01", "2023-02-28"));
+        assert_eq!(bucket(RollupPeriod::Month, "2023-12-31"), strings("2023-12", "2023-12-01", "2023-12-31"));
+    }
+
+    #[test]
+    fn rollups_clip_edge_buckets_to_the_range() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        conn.execute_batch(
+            "INSERT INTO daily_summaries (log_date, wellness_score, notes) VALUES
+                 ('2024-01-06', 2, ''), ('2024-01-07', 4, ''), ('2024-01-08', 3, ''), ('2024-01-20', 1, '');
+             INSERT INTO symptom_logs (log_date, symptom_id, severity, notes) VALUES
+                 ('2024-01-06', 1, 4, ''), ('2024-01-07', 2, 0, 'gone'), ('2024-01-08', 1, 2, '');",
+        )
+        .unwrap();
+        let range = DateRange { start_date: "2024-01-03".to_string(), end_date: "2024-01-10".to_string() };
+
+        let weeks = query_rollups(&conn, RollupPeriod::Week, &range).unwrap();
+        let summary: Vec<_> = weeks
+            .buckets
+            .iter()
+            .map(|b| (b.label.as_str(), b.start_date.as_str(), b.end_date.as_str(), b.days_logged, b.mean_wellness))
+            .collect();
+        assert_eq!(
+            summary,
+            [("2024-W01", "2024-01-03", "2024-01-07", 2, Some(3.0)), ("2024-W02", "2024-01-08", "2024-01-10", 1, Some(3.0))]
+        );
+        // Symptom 1 was present on one of the first week's two logged days,
+        // symptom 2 logged but absent
+        let first = &weeks.buckets[0].symptoms;
+        assert_eq!((first[0].symptom_id, first[0].days_present, first[0].max_severity), (1, 1, 4));
+        assert_eq!((first[0].frequency, first[0].mean_severity), (0.5, 2.0));
+        assert_eq!((first[1].symptom_id, first[1].days_present, first[1].frequency), (2, 0, 0.0));
+
+        let months = query_rollups(&conn, RollupPeriod::Month, &range).unwrap();
+        assert_eq!(months.buckets.len(), 1);
+        let january = &months.buckets[0];
+        assert_eq!((january.start_date.as_str(), january.end_date.as_str(), january.days_logged), ("2024-01-03", "2024-01-10", 3));
+        let symptom = &january.symptoms[0];
+        assert_eq!((symptom.days_present, symptom.mean_severity, symptom.max_severity), (2, 2.0, 4));
+        assert!((symptom.frequency - 2.0 / 3.0).abs() < 1e-9);
+    }
+}
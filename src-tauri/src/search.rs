@@ -0,0 +1,125 @@
+// Full-text search over lab session, symptom and daily notes via the
+// notes_fts index, which triggers in db.rs keep in step with the notes.
+
+use rusqlite::{params, Connection};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_LIMIT: i64 = 50;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct NoteHit {
+    /// "lab_session", "symptom_log" or "daily_summary".
+    pub source: String,
+    pub source_id: i64,
+    pub date: String,
+    /// Lab name for sessions, symptom name for symptom logs, empty for days.
+    pub context: String,
+    /// Excerpt around the match with matched terms wrapped in `**`.
+    pub snippet: String,
+}
+
+/// Turns free text into an FTS5 query: every word must appear, and the last
+/// one may be a prefix. Words are quoted so punctuation in the input is never
+/// parsed as query syntax.
+fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+    Some(format!("{}*", terms.join(" ")))
+}
+
+/// Notes matching every word of `query`, best match first. Each hit must
+/// still read the same in its source row, so the MCP server's policy views
+/// drop hits whose rows they hide or whose notes they redact.
+pub fn search_notes(conn: &Connection, query: &str, limit: Option<i64>) -> Result<Vec<NoteHit>, String> {
+    let Some(fts) = fts_query(query) else {
+        return Ok(Vec::new());
+    };
+    let mut stmt = conn
+        .prepare(
+            "SELECT source, source_id, note_date,
+                    CASE source
+                        WHEN 'lab_session' THEN (SELECT lab_name FROM lab_sessions WHERE id = source_id)
+                        WHEN 'symptom_log' THEN (SELECT s.name FROM symptom_logs sl
+                                                 JOIN symptoms s ON sl.symptom_id = s.id
+                                                 WHERE sl.id = source_id)
+                        ELSE ''
+                    END,
+                    snippet(notes_fts, 0, '**', '**', '…', 16)
+             FROM notes_fts
+             WHERE notes_fts MATCH ?1
+               AND notes_fts.notes IS CASE source
+                       WHEN 'lab_session' THEN (SELECT notes FROM lab_sessions WHERE id = source_id)
+                       WHEN 'symptom_log' THEN (SELECT notes FROM symptom_logs WHERE id = source_id)
+                       ELSE (SELECT notes FROM daily_summaries WHERE id = source_id)
+                   END
+             ORDER BY rank
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![fts, limit.unwrap_or(DEFAULT_LIMIT)], |row| {
+            Ok(NoteHit {
+                source: row.get(0)?,
+                source_id: row.get(1)?,
+                date: row.get(2)?,
+                context: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                snippet: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use std::path::Path;
+
+    #[test]
+    fn queries_quote_every_word_and_prefix_the_last() {
+        assert_eq!(fts_query("  \t "), None);
+        assert_eq!(fts_query("joint pain").as_deref(), Some("\"joint\" \"pain\"*"));
+        assert_eq!(fts_query("say \"hi\"").as_deref(), Some("\"say\" \"\"\"hi\"\"\"*"));
+        assert_eq!(fts_query("NEAR(a OR").as_deref(), Some("\"NEAR(a\" \"OR\"*"));
+    }
+
+    #[test]
+    fn search_finds_notes_from_every_source_and_follows_edits() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        conn.execute_batch(
+            "INSERT INTO lab_sessions (test_date, lab_name, notes) VALUES ('2024-03-01', 'Quest', 'fasting draw, arm bruised');
+             INSERT INTO symptom_logs (log_date, symptom_id, severity, notes) VALUES ('2024-03-02', 1, 4, 'worse after the draw');
+             INSERT INTO daily_summaries (log_date, notes) VALUES ('2024-03-03', 'drew a bath: helped');",
+        )
+        .unwrap();
+        let sources = |query: &str| -> Vec<(String, String)> {
+            let mut hits: Vec<(String, String)> =
+                search_notes(&conn, query, None).unwrap().into_iter().map(|h| (h.source, h.context)).collect();
+            hits.sort();
+            hits
+        };
+        // The last word matches as a prefix, so "dr" also reaches "drew"
+        assert_eq!(
+            sources("draw"),
+            [("lab_session".to_string(), "Quest".to_string()), ("symptom_log".to_string(), "Fatigue".to_string())]
+        );
+        assert_eq!(sources("dr").len(), 3);
+        assert_eq!(sources("bath: helped"), [("daily_summary".to_string(), String::new())]);
+        // Query syntax in the input is matched as text rather than failing
+        assert!(sources("\"unbalanced AND NOT(").is_empty());
+        assert!(search_notes(&conn, "", None).unwrap().is_empty());
+        assert_eq!(search_notes(&conn, "dr", Some(1)).unwrap().len(), 1);
+
+        conn.execute("UPDATE lab_sessions SET notes = 'no issues' WHERE lab_name = 'Quest'", []).unwrap();
+        assert_eq!(sources("draw"), [("symptom_log".to_string(), "Fatigue".to_string())]);
+        assert_eq!(search_notes(&conn, "issues", None).unwrap()[0].snippet, "no **issues**");
+    }
+}
@@ -0,0 +1,289 @@
+// Small numeric helpers shared by the trend and analysis commands.
+
+use chrono::{Duration, NaiveDate};
+
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+pub fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        Some((sorted[mid - 1] + sorted[mid]) / 2.0)
+    } else {
+        Some(sorted[mid])
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RangeTime {
+    pub days_assessed: i64,
+    pub days_in_range: i64,
+    pub days_below: i64,
+    pub days_above: i64,
+}
+
+/// Time in range by the Rosendaal method: values are linearly interpolated
+/// day by day between consecutive measurements, and each interpolated day
+/// falling within `start..=end` is classified against `low..=high`.
+/// `points` must be sorted by date.
+pub fn rosendaal(
+    points: &[(NaiveDate, f64)],
+    low: f64,
+    high: f64,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> RangeTime {
+    let mut out = RangeTime::default();
+    for pair in points.windows(2) {
+        let (d0, v0) = pair[0];
+        let (d1, v1) = pair[1];
+        let gap = (d1 - d0).num_days();
+        for k in 0..gap {
+            let day = d0 + Duration::days(k);
+            if day < start || day > end {
+                continue;
+            }
+            let v = v0 + (v1 - v0) * k as f64 / gap as f64;
+            out.days_assessed += 1;
+            if v < low {
+                out.days_below += 1;
+            } else if v > high {
+                out.days_above += 1;
+            } else {
+                out.days_in_range += 1;
+            }
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Run {
+    pub first: usize,
+    pub last: usize,
+    pub below: bool,
+}
+
+/// Maximal runs of consecutive values on the same side outside `low..=high`.
+pub fn out_of_range_runs(values: &[f64], low: f64, high: f64) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    let mut current: Option<Run> = None;
+    for (i, &v) in values.iter().enumerate() {
+        let side = if v < low {
+            Some(true)
+        } else if v > high {
+            Some(false)
+        } else {
+            None
+        };
+        current = match (current, side) {
+            (Some(run), Some(below)) if run.below == below => Some(Run { last: i, ..run }),
+            (prev, side) => {
+                runs.extend(prev);
+                side.map(|below| Run { first: i, last: i, below })
+            }
+        };
+    }
+    runs.extend(current);
+    runs
+}
+
+/// Pearson product-moment correlation. `None` when there are fewer than
+/// three pairs or either side has no variance.
+pub fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() != ys.len() || xs.len() < 3 {
+        return None;
+    }
+    let mx = mean(xs)?;
+    let my = mean(ys)?;
+    let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        sxy += (x - mx) * (y - my);
+        sxx += (x - mx).powi(2);
+        syy += (y - my).powi(2);
+    }
+    if sxx == 0.0 || syy == 0.0 {
+        return None;
+    }
+    Some(sxy / (sxx * syy).sqrt())
+}
+
+/// Ranks starting at 1, with tied values sharing their average rank.
+pub fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].partial_cmp(&values[b]).unwrap_or(std::cmp::Ordering::Equal));
+    let mut out = vec![0.0; values.len()];
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i;
+        while j + 1 < order.len() && values[order[j + 1]] == values[order[i]] {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        for &idx in &order[i..=j] {
+            out[idx] = rank;
+        }
+        i = j + 1;
+    }
+    out
+}
+
+/// Spearman rank correlation: Pearson over tie-averaged ranks.
+pub fn spearman(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() != ys.len() {
+        return None;
+    }
+    pearson(&ranks(xs), &ranks(ys))
+}
+
+/// Least-squares slope of `ys` against `xs`. `None` with fewer than two
+/// points or no spread in `xs`.
+pub fn linear_slope(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() != ys.len() || xs.len() < 2 {
+        return None;
+    }
+    let mx = mean(xs)?;
+    let my = mean(ys)?;
+    let (mut sxy, mut sxx) = (0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        sxy += (x - mx) * (y - my);
+        sxx += (x - mx).powi(2);
+    }
+    if sxx == 0.0 {
+        return None;
+    }
+    Some(sxy / sxx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn time_in_range(t: RangeTime) -> (i64, i64, i64, i64) {
+        (t.days_assessed, t.days_in_range, t.days_below, t.days_above)
+    }
+
+    #[test]
+    fn mean_and_median_handle_empty_and_even_inputs() {
+        assert_eq!(mean(&[]), None);
+        assert_eq!(median(&[]), None);
+        assert_eq!(mean(&[1.0, 2.0, 6.0]), Some(3.0));
+        assert_eq!(median(&[6.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+
+    #[test]
+    fn rosendaal_interpolates_between_measurements() {
+        // 2.0, 2.5, 3.0, 3.5 on Jan 1-4; the last measurement day starts the next gap
+        let points = [(day("2024-01-01"), 2.0), (day("2024-01-05"), 4.0)];
+        let t = rosendaal(&points, 2.0, 3.0, day("2024-01-01"), day("2024-01-31"));
+        assert_eq!(time_in_range(t), (4, 3, 0, 1));
+
+        let points = [(day("2024-01-01"), 1.0), (day("2024-01-03"), 1.5), (day("2024-01-05"), 2.5)];
+        let t = rosendaal(&points, 2.0, 3.0, day("2024-01-01"), day("2024-01-31"));
+        // 1.0, 1.25, 1.5, 2.0
+        assert_eq!(time_in_range(t), (4, 1, 3, 0));
+    }
+
+    #[test]
+    fn rosendaal_counts_only_days_inside_the_range() {
+        // Gap straddling the start: Jan 1-10 interpolate 1.0 to 2.8 in 0.2 steps,
+        // of which Jan 6-10 (2.0..=2.8) fall inside the range
+        let points = [(day("2024-01-01"), 1.0), (day("2024-01-11"), 3.0)];
+        let t = rosendaal(&points, 2.0, 3.0, day("2024-01-06"), day("2024-01-31"));
+        assert_eq!(time_in_range(t), (5, 5, 0, 0));
+
+        // Gap straddling the end: only Jan 1-2 (1.0, 1.2) are inside
+        let t = rosendaal(&points, 2.0, 3.0, day("2023-12-01"), day("2024-01-02"));
+        assert_eq!(time_in_range(t), (2, 0, 2, 0));
+    }
+
+    #[test]
+    fn rosendaal_needs_two_measurements() {
+        let range = (day("2024-01-01"), day("2024-01-31"));
+        assert_eq!(time_in_range(rosendaal(&[], 2.0, 3.0, range.0, range.1)), (0, 0, 0, 0));
+        let single = [(day("2024-01-10"), 2.5)];
+        assert_eq!(time_in_range(rosendaal(&single, 2.0, 3.0, range.0, range.1)), (0, 0, 0, 0));
+    }
+
+    #[test]
+    fn out_of_range_runs_split_by_side() {
+        let runs = out_of_range_runs(&[1.5, 1.8, 2.5, 3.5, 3.6, 1.0, 4.0], 2.0, 3.0);
+        let runs: Vec<_> = runs.iter().map(|r| (r.first, r.last, r.below)).collect();
+        assert_eq!(runs, [(0, 1, true), (3, 4, false), (5, 5, true), (6, 6, false)]);
+
+        // Bounds are in range
+        assert!(out_of_range_runs(&[2.0, 3.0], 2.0, 3.0).is_empty());
+        assert!(out_of_range_runs(&[], 2.0, 3.0).is_empty());
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("expected a value");
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn pearson_matches_hand_computed_values() {
+        assert_close(pearson(&[1.0, 2.0, 3.0], &[2.0, 4.0, 6.0]), 1.0);
+        assert_close(pearson(&[1.0, 2.0, 3.0], &[6.0, 4.0, 2.0]), -1.0);
+        // Deviations (-1.5, -0.5, 0.5, 1.5) and (-0.5, -1.5, 1.5, 0.5): 3 / sqrt(5 * 5)
+        assert_close(pearson(&[1.0, 2.0, 3.0, 4.0], &[2.0, 1.0, 4.0, 3.0]), 0.6);
+    }
+
+    #[test]
+    fn pearson_needs_three_points_and_spread_on_both_sides() {
+        assert_eq!(pearson(&[1.0, 2.0], &[1.0, 2.0]), None);
+        assert_eq!(pearson(&[1.0, 2.0, 3.0], &[5.0, 5.0, 5.0]), None);
+        assert_eq!(pearson(&[4.0, 4.0, 4.0], &[1.0, 2.0, 3.0]), None);
+        assert_eq!(pearson(&[1.0, 2.0, 3.0], &[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn ranks_average_ties() {
+        assert_eq!(ranks(&[10.0, 20.0, 20.0, 30.0]), [1.0, 2.5, 2.5, 4.0]);
+        assert_eq!(ranks(&[3.0, 1.0, 3.0, 3.0]), [3.0, 1.0, 3.0, 3.0]);
+        assert!(ranks(&[]).is_empty());
+    }
+
+    #[test]
+    fn spearman_correlates_ranks() {
+        // Monotonic but not linear
+        assert_close(spearman(&[1.0, 2.0, 3.0, 4.0], &[1.0, 4.0, 9.0, 100.0]), 1.0);
+        // Ranks (1, 2.5, 2.5, 4) against (1, 2, 3, 4): 4.5 / sqrt(4.5 * 5)
+        assert_close(spearman(&[1.0, 2.0, 2.0, 3.0], &[1.0, 2.0, 3.0, 4.0]), 0.9_f64.sqrt());
+        // All tied ranks have no spread
+        assert_eq!(spearman(&[1.0, 2.0, 3.0], &[7.0, 7.0, 7.0]), None);
+        assert_eq!(spearman(&[1.0, 2.0], &[2.0, 1.0]), None);
+    }
+
+    #[test]
+    fn linear_slope_fits_least_squares() {
+        assert_close(linear_slope(&[0.0, 1.0, 2.0], &[1.0, 3.0, 5.0]), 2.0);
+        assert_close(linear_slope(&[0.0, 10.0, 20.0, 30.0], &[5.0, 5.0, 5.0, 5.0]), 0.0);
+        // Deviations (-1.5, -0.5, 0.5, 1.5) and (-1.5, -0.5, -0.5, 2.5): 6 / 5
+        assert_close(linear_slope(&[0.0, 1.0, 2.0, 3.0], &[1.0, 2.0, 2.0, 5.0]), 1.2);
+        // Two points are enough
+        assert_close(linear_slope(&[0.0, 4.0], &[3.0, 1.0]), -0.5);
+    }
+
+    #[test]
+    fn linear_slope_needs_two_points_with_spread() {
+        assert_eq!(linear_slope(&[], &[]), None);
+        assert_eq!(linear_slope(&[1.0], &[2.0]), None);
+        assert_eq!(linear_slope(&[3.0, 3.0, 3.0], &[1.0, 2.0, 3.0]), None);
+        assert_eq!(linear_slope(&[1.0, 2.0], &[1.0]), None);
+    }
+}
@@ -0,0 +1,496 @@
+// Day-level symptom logging: the time slot and body location vocabularies,
+// entry validation, and reading and writing a whole day's log. Every write
+// records its `source` ("app" or "mcp") on the rows it touches.
+
+use crate::date_range;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+
+pub const TIME_SLOTS: [(&str, &str); 4] = [
+    ("morning", "08:00"),
+    ("afternoon", "14:00"),
+    ("evening", "19:00"),
+    ("night", "23:00"),
+];
+
+/// Fixed anatomical list for symptom locations: (key, label, is_joint).
+pub const BODY_LOCATIONS: [(&str, &str, bool); 30] = [
+    ("head", "Head", false),
+    ("face", "Face", false),
+    ("eyes", "Eyes", false),
+    ("mouth", "Mouth", false),
+    ("neck", "Neck", false),
+    ("chest", "Chest", false),
+    ("abdomen", "Abdomen", false),
+    ("upper_back", "Upper back", false),
+    ("lower_back", "Lower back", false),
+    ("left_shoulder", "Left shoulder", true),
+    ("right_shoulder", "Right shoulder", true),
+    ("left_elbow", "Left elbow", true),
+    ("right_elbow", "Right elbow", true),
+    ("left_wrist", "Left wrist", true),
+    ("right_wrist", "Right wrist", true),
+    ("left_hand", "Left hand / fingers", true),
+    ("right_hand", "Right hand / fingers", true),
+    ("left_hip", "Left hip", true),
+    ("right_hip", "Right hip", true),
+    ("left_knee", "Left knee", true),
+    ("right_knee", "Right knee", true),
+    ("left_ankle", "Left ankle", true),
+    ("right_ankle", "Right ankle", true),
+    ("left_foot", "Left foot / toes", true),
+    ("right_foot", "Right foot / toes", true),
+    ("arms", "Arms", false),
+    ("legs", "Legs", false),
+    ("skin_sun_exposed", "Sun-exposed skin", false),
+    ("scalp", "Scalp", false),
+    ("whole_body", "Whole body", false),
+];
+
+/// Wellness score of a day without a daily summary: what reads report and
+/// what writes that don't set one store.
+pub const DEFAULT_WELLNESS: i64 = 5;
+
+/// One symptom in a day's log. Like `intraday`, the detail fields are `None`
+/// when a client leaves them out, which keeps what is stored for the symptom;
+/// `duration_minutes` and `onset_time` are cleared with an explicit `null`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SymptomEntry {
+    pub symptom_id: i64,
+    pub severity: i64,
+    pub notes: String,
+    #[serde(default)]
+    pub intraday: Option<Vec<IntradayEntry>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_locations: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub duration_minutes: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub onset_time: Option<Option<String>>,
+}
+
+/// Reads a field that is present, `null` included, as `Some`; a missing field
+/// falls back to `None` through `#[serde(default)]`.
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// A symptom's stored details, kept by saves that leave them out.
+#[derive(Default)]
+struct StoredDetails {
+    body_locations: Vec<String>,
+    duration_minutes: Option<i64>,
+    onset_time: Option<String>,
+}
+
+/// One reading within a day. `time_of_day` is either "HH:MM" or one of the
+/// `TIME_SLOTS` names.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IntradayEntry {
+    pub time_of_day: String,
+    pub severity: i64,
+    pub notes: String,
+}
+
+/// A day's log as stored, including intraday readings, so it can be written
+/// back unchanged with `save_day`. Without a daily summary row, `wellness`
+/// and `notes` are placeholders and `has_summary` is false, so a restore
+/// removes the summary `save_day` writes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DaySnapshot {
+    pub wellness: i64,
+    pub notes: String,
+    pub source: String,
+    pub entries: Vec<SymptomEntry>,
+    // Snapshots recorded before this field always had their summary restored
+    #[serde(default = "has_summary_default")]
+    pub has_summary: bool,
+}
+
+fn has_summary_default() -> bool {
+    true
+}
+
+fn validate_severity(severity: i64) -> Result<(), String> {
+    if (0..=10).contains(&severity) {
+        Ok(())
+    } else {
+        Err(format!("Severity must be between 0 and 10, got {}", severity))
+    }
+}
+
+fn validate_time_of_day(time_of_day: &str) -> Result<(), String> {
+    let is_slot = TIME_SLOTS.iter().any(|(slot, _)| *slot == time_of_day);
+    if is_slot || chrono::NaiveTime::parse_from_str(time_of_day, "%H:%M").is_ok() {
+        Ok(())
+    } else {
+        Err(format!("Invalid time of day: {}", time_of_day))
+    }
+}
+
+fn validate_entry_details(entry: &SymptomEntry) -> Result<(), String> {
+    validate_severity(entry.severity)?;
+    for location in entry.body_locations.iter().flatten() {
+        if !BODY_LOCATIONS.iter().any(|(key, _, _)| key == location) {
+            return Err(format!("Unknown body location: {}", location));
+        }
+    }
+    if entry.duration_minutes.flatten().is_some_and(|m| m < 0) {
+        return Err("Duration must not be negative".to_string());
+    }
+    if let Some(Some(onset)) = &entry.onset_time {
+        if chrono::NaiveTime::parse_from_str(onset, "%H:%M").is_err() {
+            return Err(format!("Invalid onset time: {}", onset));
+        }
+    }
+    Ok(())
+}
+
+pub fn load_locations(conn: &Connection, date: &str) -> Result<HashMap<i64, Vec<String>>, String> {
+    let mut stmt = conn
+        .prepare("SELECT symptom_id, location FROM symptom_log_locations WHERE log_date = ?1 ORDER BY location")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![date], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    let mut by_symptom: HashMap<i64, Vec<String>> = HashMap::new();
+    for row in rows {
+        let (symptom_id, location) = row.map_err(|e| e.to_string())?;
+        by_symptom.entry(symptom_id).or_default().push(location);
+    }
+    Ok(by_symptom)
+}
+
+fn load_details(conn: &Connection, date: &str) -> Result<HashMap<i64, StoredDetails>, String> {
+    let mut locations = load_locations(conn, date)?;
+    let mut stmt = conn
+        .prepare("SELECT symptom_id, duration_minutes, onset_time FROM symptom_logs WHERE log_date = ?1")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![date], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?;
+    let mut by_symptom = HashMap::new();
+    for row in rows {
+        let (symptom_id, duration_minutes, onset_time) = row.map_err(|e| e.to_string())?;
+        let body_locations = locations.remove(&symptom_id).unwrap_or_default();
+        by_symptom.insert(symptom_id, StoredDetails { body_locations, duration_minutes, onset_time });
+    }
+    Ok(by_symptom)
+}
+
+pub fn time_sort_key(time_of_day: &str) -> &str {
+    TIME_SLOTS
+        .iter()
+        .find(|(slot, _)| *slot == time_of_day)
+        .map(|(_, time)| *time)
+        .unwrap_or(time_of_day)
+}
+
+/// Collapses intraday severities into the day-level value according to the
+/// `symptom_day_aggregate` setting ("max" or "mean").
+fn aggregate_day_severity(conn: &Connection, severities: &[i64]) -> i64 {
+    let method: String = conn
+        .query_row("SELECT value FROM settings WHERE key = 'symptom_day_aggregate'", [], |r| r.get(0))
+        .unwrap_or_default();
+    if method == "mean" {
+        let sum: i64 = severities.iter().sum();
+        (sum as f64 / severities.len() as f64).round() as i64
+    } else {
+        severities.iter().copied().max().unwrap_or(0)
+    }
+}
+
+pub fn load_intraday(conn: &Connection, date: &str) -> Result<HashMap<i64, Vec<IntradayEntry>>, String> {
+    let mut stmt = conn
+        .prepare("SELECT symptom_id, time_of_day, severity, notes FROM symptom_log_entries WHERE log_date = ?1")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![date], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                IntradayEntry {
+                    time_of_day: row.get(1)?,
+                    severity: row.get(2)?,
+                    notes: row.get(3)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut by_symptom: HashMap<i64, Vec<IntradayEntry>> = HashMap::new();
+    for row in rows {
+        let (symptom_id, entry) = row.map_err(|e| e.to_string())?;
+        by_symptom.entry(symptom_id).or_default().push(entry);
+    }
+    for entries in by_symptom.values_mut() {
+        entries.sort_by(|a, b| time_sort_key(&a.time_of_day).cmp(time_sort_key(&b.time_of_day)));
+    }
+    Ok(by_symptom)
+}
+
+fn validate_wellness(wellness: i64) -> Result<(), String> {
+    if (1..=5).contains(&wellness) {
+        Ok(())
+    } else {
+        Err(format!("Wellness score must be between 1 and 5, got {}", wellness))
+    }
+}
+
+/// The stored log for `date`, or `None` if nothing was logged that day.
+pub fn day_snapshot(conn: &Connection, date: &str) -> Result<Option<DaySnapshot>, String> {
+    let summary: Option<(i64, String, String)> = conn
+        .query_row(
+            "SELECT wellness_score, notes, source FROM daily_summaries WHERE log_date = ?1",
+            params![date],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .ok();
+
+    let mut intraday = load_intraday(conn, date)?;
+    let mut locations = load_locations(conn, date)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT symptom_id, severity, notes, duration_minutes, onset_time, source
+             FROM symptom_logs WHERE log_date = ?1 ORDER BY symptom_id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<(SymptomEntry, String)> = stmt
+        .query_map(params![date], |row| {
+            Ok((
+                SymptomEntry {
+                    symptom_id: row.get(0)?,
+                    severity: row.get(1)?,
+                    notes: row.get(2)?,
+                    intraday: None,
+                    body_locations: None,
+                    duration_minutes: Some(row.get(3)?),
+                    onset_time: Some(row.get(4)?),
+                },
+                row.get(5)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    if summary.is_none() && rows.is_empty() {
+        return Ok(None);
+    }
+    let symptom_source = rows.first().map(|(_, source)| source.clone());
+    let entries = rows
+        .into_iter()
+        .map(|(mut entry, _)| {
+            entry.intraday = Some(intraday.remove(&entry.symptom_id).unwrap_or_default());
+            entry.body_locations = Some(locations.remove(&entry.symptom_id).unwrap_or_default());
+            entry
+        })
+        .collect();
+    let has_summary = summary.is_some();
+    let (wellness, notes, source) = summary.unwrap_or_else(|| {
+        (DEFAULT_WELLNESS, String::new(), symptom_source.unwrap_or_default())
+    });
+    Ok(Some(DaySnapshot { wellness, notes, source, entries, has_summary }))
+}
+
+/// Replaces the day's log. An entry with `intraday: Some(..)` replaces that
+/// symptom's intraday readings and derives the day severity from them; with
+/// `intraday: None` any existing readings are kept (and still drive the day
+/// value), so day-level clients don't clobber detail they can't see. An
+/// explicit severity of 0 with `intraday: None` marks the symptom absent and
+/// drops its readings, so day-level clients can still clear it.
+pub fn save_day(
+    conn: &Connection,
+    date: &str,
+    entries: &[SymptomEntry],
+    wellness: i64,
+    notes: &str,
+    source: &str,
+) -> Result<(), String> {
+    date_range::parse_date(date)?;
+    validate_wellness(wellness)?;
+    for entry in entries {
+        validate_entry_details(entry)?;
+        for reading in entry.intraday.iter().flatten() {
+            validate_time_of_day(&reading.time_of_day)?;
+            validate_severity(reading.severity)?;
+        }
+    }
+
+    let mut existing_intraday = load_intraday(conn, date)?;
+    let mut existing_details = load_details(conn, date)?;
+    conn.execute("DELETE FROM symptom_logs WHERE log_date = ?1", params![date])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM symptom_log_entries WHERE log_date = ?1", params![date])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM symptom_log_locations WHERE log_date = ?1", params![date])
+        .map_err(|e| e.to_string())?;
+
+    for entry in entries {
+        let intraday: Vec<IntradayEntry> = match &entry.intraday {
+            Some(readings) => readings
+                .iter()
+                .filter(|r| r.severity > 0 || !r.notes.is_empty())
+                .cloned()
+                .collect(),
+            None if entry.severity == 0 => Vec::new(),
+            None => existing_intraday.remove(&entry.symptom_id).unwrap_or_default(),
+        };
+        for reading in &intraday {
+            conn.execute(
+                "INSERT INTO symptom_log_entries (log_date, symptom_id, time_of_day, severity, notes) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![date, entry.symptom_id, reading.time_of_day, reading.severity, reading.notes],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+
+        let severity = if intraday.is_empty() {
+            entry.severity
+        } else {
+            let severities: Vec<i64> = intraday.iter().map(|r| r.severity).collect();
+            aggregate_day_severity(conn, &severities)
+        };
+        // Stored details are kept like readings, and dropped with them
+        let stored = match existing_details.remove(&entry.symptom_id) {
+            Some(_) if entry.severity == 0 && entry.intraday.is_none() => StoredDetails::default(),
+            stored => stored.unwrap_or_default(),
+        };
+        let body_locations = entry.body_locations.clone().unwrap_or(stored.body_locations);
+        let duration_minutes = entry.duration_minutes.unwrap_or(stored.duration_minutes);
+        let onset_time = entry.onset_time.clone().unwrap_or(stored.onset_time);
+        let has_details = !body_locations.is_empty() || duration_minutes.is_some() || onset_time.is_some();
+        if severity > 0 || !entry.notes.is_empty() || !intraday.is_empty() || has_details {
+            conn.execute(
+                "INSERT INTO symptom_logs (log_date, symptom_id, severity, notes, duration_minutes, onset_time, source)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![date, entry.symptom_id, severity, entry.notes, duration_minutes, onset_time, source],
+            )
+            .map_err(|e| e.to_string())?;
+            for location in &body_locations {
+                conn.execute(
+                    "INSERT OR IGNORE INTO symptom_log_locations (log_date, symptom_id, location) VALUES (?1, ?2, ?3)",
+                    params![date, entry.symptom_id, location],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    // Drop tags on symptoms that are no longer logged for the day
+    conn.execute(
+        "DELETE FROM symptom_log_tags WHERE log_date = ?1
+           AND symptom_id NOT IN (SELECT symptom_id FROM symptom_logs WHERE log_date = ?1)",
+        params![date],
+    )
+    .map_err(|e| e.to_string())?;
+
+    save_daily_summary(conn, date, wellness, notes, source)
+}
+
+/// Writes only the day's wellness score and notes, leaving symptom entries
+/// untouched.
+pub fn save_daily_summary(conn: &Connection, date: &str, wellness: i64, notes: &str, source: &str) -> Result<(), String> {
+    date_range::parse_date(date)?;
+    validate_wellness(wellness)?;
+    conn.execute(
+        "INSERT OR REPLACE INTO daily_summaries (log_date, wellness_score, notes, source) VALUES (?1, ?2, ?3, ?4)",
+        params![date, wellness, notes, source],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Removes everything logged for `date`: symptom entries, intraday readings,
+/// locations, symptom tags and the daily summary.
+pub fn delete_day(conn: &Connection, date: &str) -> Result<(), String> {
+    for table in ["symptom_logs", "symptom_log_entries", "symptom_log_locations", "symptom_log_tags", "daily_summaries"] {
+        conn.execute(&format!("DELETE FROM {} WHERE log_date = ?1", table), params![date])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use std::path::Path;
+
+    fn entry(symptom_id: i64, severity: i64, intraday: Option<Vec<IntradayEntry>>) -> SymptomEntry {
+        SymptomEntry {
+            symptom_id,
+            severity,
+            notes: String::new(),
+            intraday,
+            body_locations: None,
+            duration_minutes: None,
+            onset_time: None,
+        }
+    }
+
+    fn reading(time_of_day: &str, severity: i64) -> IntradayEntry {
+        IntradayEntry { time_of_day: time_of_day.to_string(), severity, notes: String::new() }
+    }
+
+    #[test]
+    fn day_level_saves_keep_or_clear_intraday_readings() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        let date = "2024-03-01";
+        let readings = vec![reading("morning", 6), reading("evening", 2)];
+        save_day(&conn, date, &[entry(1, 0, Some(readings))], 3, "", "app").unwrap();
+        let stored = |conn: &Connection| -> Option<i64> {
+            conn.query_row("SELECT severity FROM symptom_logs WHERE log_date = ?1 AND symptom_id = 1", [date], |r| r.get(0))
+                .ok()
+        };
+        assert_eq!(stored(&conn), Some(6));
+
+        // Present without readings: the stored readings still set the severity
+        save_day(&conn, date, &[entry(1, 1, None)], 3, "", "app").unwrap();
+        assert_eq!(stored(&conn), Some(6));
+        assert_eq!(load_intraday(&conn, date).unwrap()[&1].len(), 2);
+
+        // Explicitly absent: the readings go too
+        save_day(&conn, date, &[entry(1, 0, None)], 3, "", "app").unwrap();
+        assert_eq!(stored(&conn), None);
+        assert!(load_intraday(&conn, date).unwrap().is_empty());
+    }
+
+    #[test]
+    fn saves_without_details_keep_the_stored_ones() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        let date = "2024-03-01";
+        let detailed = SymptomEntry {
+            body_locations: Some(vec!["head".to_string()]),
+            duration_minutes: Some(Some(45)),
+            onset_time: Some(Some("07:30".to_string())),
+            ..entry(1, 4, None)
+        };
+        save_day(&conn, date, &[detailed], 3, "", "mcp").unwrap();
+        let details = |conn: &Connection| -> (Vec<String>, Option<i64>, Option<String>) {
+            let stored = load_details(conn, date).unwrap().remove(&1).unwrap_or_default();
+            (stored.body_locations, stored.duration_minutes, stored.onset_time)
+        };
+
+        // The app form leaves the fields out
+        let from_app: SymptomEntry =
+            serde_json::from_value(serde_json::json!({ "symptom_id": 1, "severity": 5, "notes": "" })).unwrap();
+        save_day(&conn, date, &[from_app], 3, "", "app").unwrap();
+        assert_eq!(details(&conn), (vec!["head".to_string()], Some(45), Some("07:30".to_string())));
+
+        // An explicit null clears one field and leaves the others
+        let cleared: SymptomEntry =
+            serde_json::from_value(serde_json::json!({ "symptom_id": 1, "severity": 5, "notes": "", "onset_time": null }))
+                .unwrap();
+        save_day(&conn, date, &[cleared], 3, "", "app").unwrap();
+        assert_eq!(details(&conn), (vec!["head".to_string()], Some(45), None));
+
+        // Marking the symptom absent drops its details
+        save_day(&conn, date, &[entry(1, 0, None)], 3, "", "app").unwrap();
+        assert_eq!(details(&conn), (Vec::new(), None, None));
+    }
+}
@@ -0,0 +1,260 @@
+// Free-form tags on days, lab sessions and symptom logs ("travel", "stress",
+// "pre-infusion"), used to filter trends and exports and to compare tagged
+// against untagged days. Filters work by date: a date carries a tag when the
+// day, a symptom log or a lab session on it is tagged.
+
+use crate::analysis::{self, SymptomTarget};
+use crate::date_range::DateRange;
+use crate::stats;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Tag {
+    pub id: Option<i64>,
+    pub name: String,
+    #[serde(default)]
+    pub color: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagSymptomComparison {
+    pub symptom_id: i64,
+    pub name: String,
+    pub mean_severity_tagged: Option<f64>,
+    pub mean_severity_untagged: Option<f64>,
+    pub frequency_tagged: Option<f64>,
+    pub frequency_untagged: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagComparison {
+    pub tag: Tag,
+    pub start_date: String,
+    pub end_date: String,
+    pub tagged_days: i64,
+    pub untagged_days: i64,
+    pub mean_wellness_tagged: Option<f64>,
+    pub mean_wellness_untagged: Option<f64>,
+    pub symptoms: Vec<TagSymptomComparison>,
+}
+
+fn row_to_tag(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: Some(row.get(0)?),
+        name: row.get(1)?,
+        color: row.get(2)?,
+    })
+}
+
+pub fn get_tag(conn: &Connection, tag_id: i64) -> Result<Tag, String> {
+    conn.query_row("SELECT id, name, color FROM tags WHERE id = ?1", params![tag_id], row_to_tag)
+        .map_err(|_| format!("Unknown tag id: {}", tag_id))
+}
+
+pub fn all_tags(conn: &Connection) -> Result<Vec<Tag>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, name, color FROM tags ORDER BY name COLLATE NOCASE")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_tag).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn day_tags(conn: &Connection, date: &str) -> Result<Vec<Tag>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.name, t.color FROM day_tags d JOIN tags t ON d.tag_id = t.id
+             WHERE d.log_date = ?1 ORDER BY t.name COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![date], row_to_tag).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn lab_session_tags(conn: &Connection, session_id: i64) -> Result<Vec<Tag>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.name, t.color FROM lab_session_tags l JOIN tags t ON l.tag_id = t.id
+             WHERE l.session_id = ?1 ORDER BY t.name COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map(params![session_id], row_to_tag).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Tags on each symptom logged on `date`, as (symptom_id, tag) pairs.
+pub fn symptom_log_tags(conn: &Connection, date: &str) -> Result<Vec<(i64, Tag)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.symptom_id, t.id, t.name, t.color FROM symptom_log_tags s JOIN tags t ON s.tag_id = t.id
+             WHERE s.log_date = ?1 ORDER BY s.symptom_id, t.name COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![date], |row| {
+            Ok((
+                row.get(0)?,
+                Tag {
+                    id: Some(row.get(1)?),
+                    name: row.get(2)?,
+                    color: row.get(3)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Dates carrying `tag_id`: tagged days, days with a tagged symptom log, and
+/// the draw dates of tagged lab sessions.
+pub fn tagged_dates(conn: &Connection, tag_id: i64) -> Result<HashSet<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT log_date FROM day_tags WHERE tag_id = ?1
+             UNION
+             SELECT log_date FROM symptom_log_tags WHERE tag_id = ?1
+             UNION
+             SELECT s.test_date FROM lab_session_tags t
+             JOIN lab_sessions s ON t.session_id = s.id
+             WHERE t.tag_id = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![tag_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<HashSet<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Mean wellness and per-symptom severity on logged days carrying the tag
+/// versus the other logged days in `range`. Symptoms never logged in the
+/// range are left out.
+pub fn compare_tag(conn: &Connection, tag_id: i64, range: &DateRange) -> Result<TagComparison, String> {
+    let tag = get_tag(conn, tag_id)?;
+    let tagged = tagged_dates(conn, tag_id)?;
+    let is_tagged = |day: &chrono::NaiveDate| tagged.contains(&day.format("%Y-%m-%d").to_string());
+
+    let split = |target: SymptomTarget| -> Result<(Vec<f64>, Vec<f64>), String> {
+        let (mut t, mut u) = (Vec::new(), Vec::new());
+        for (day, value) in analysis::daily_series(conn, target, &range.start_date, &range.end_date)? {
+            if is_tagged(&day) {
+                t.push(value);
+            } else {
+                u.push(value);
+            }
+        }
+        Ok((t, u))
+    };
+    let frequency = |values: &[f64]| {
+        (!values.is_empty()).then(|| values.iter().filter(|v| **v > 0.0).count() as f64 / values.len() as f64)
+    };
+
+    let (wellness_tagged, wellness_untagged) = split(SymptomTarget::Wellness)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT s.id, s.name FROM symptom_logs sl
+             JOIN symptoms s ON sl.symptom_id = s.id
+             WHERE sl.log_date >= ?1 AND sl.log_date <= ?2
+             ORDER BY s.sort_order",
+        )
+        .map_err(|e| e.to_string())?;
+    let logged: Vec<(i64, String)> = stmt
+        .query_map(params![range.start_date, range.end_date], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let mut symptoms = Vec::new();
+    for (symptom_id, name) in logged {
+        let (t, u) = split(SymptomTarget::Symptom(symptom_id))?;
+        symptoms.push(TagSymptomComparison {
+            symptom_id,
+            name,
+            mean_severity_tagged: stats::mean(&t),
+            mean_severity_untagged: stats::mean(&u),
+            frequency_tagged: frequency(&t),
+            frequency_untagged: frequency(&u),
+        });
+    }
+
+    // Logged days are any day with a summary or a symptom entry
+    let mut stmt = conn
+        .prepare(
+            "SELECT log_date FROM daily_summaries WHERE log_date >= ?1 AND log_date <= ?2
+             UNION
+             SELECT log_date FROM symptom_logs WHERE log_date >= ?1 AND log_date <= ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let days: Vec<String> = stmt
+        .query_map(params![range.start_date, range.end_date], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let tagged_days = days.iter().filter(|d| tagged.contains(*d)).count() as i64;
+
+    Ok(TagComparison {
+        tag,
+        start_date: range.start_date.clone(),
+        end_date: range.end_date.clone(),
+        tagged_days,
+        untagged_days: days.len() as i64 - tagged_days,
+        mean_wellness_tagged: stats::mean(&wellness_tagged),
+        mean_wellness_untagged: stats::mean(&wellness_untagged),
+        symptoms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use std::path::Path;
+
+    /// "travel" tags Mar 2 directly, Mar 3 through a symptom log and Mar 5
+    /// through a lab session. Wellness is 7 on Mar 1 and 4 on Mar 2-3, with
+    /// Fatigue at 6 on Mar 3 only.
+    fn seed(conn: &Connection) {
+        conn.execute_batch(
+            "INSERT INTO tags (name) VALUES ('travel');
+             INSERT INTO daily_summaries (log_date, wellness_score) VALUES
+                ('2024-03-01', 7), ('2024-03-02', 4), ('2024-03-03', 4);
+             INSERT INTO symptom_logs (log_date, symptom_id, severity) VALUES ('2024-03-03', 1, 6);
+             INSERT INTO lab_sessions (test_date) VALUES ('2024-03-05');
+             INSERT INTO day_tags (log_date, tag_id) VALUES ('2024-03-02', 1);
+             INSERT INTO symptom_log_tags (log_date, symptom_id, tag_id) VALUES ('2024-03-03', 1, 1);
+             INSERT INTO lab_session_tags (session_id, tag_id) VALUES (1, 1);",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn dates_carry_day_symptom_and_lab_tags() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        seed(&conn);
+        let mut dates: Vec<String> = tagged_dates(&conn, 1).unwrap().into_iter().collect();
+        dates.sort();
+        assert_eq!(dates, ["2024-03-02", "2024-03-03", "2024-03-05"]);
+        assert!(tagged_dates(&conn, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn comparison_splits_logged_days_by_tag() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        seed(&conn);
+        let range = DateRange { start_date: "2024-03-01".to_string(), end_date: "2024-03-31".to_string() };
+        let comparison = compare_tag(&conn, 1, &range).unwrap();
+        // Mar 5 has a tagged lab but nothing logged, so it isn't counted
+        assert_eq!((comparison.tagged_days, comparison.untagged_days), (2, 1));
+        assert_eq!(comparison.mean_wellness_tagged, Some(4.0));
+        assert_eq!(comparison.mean_wellness_untagged, Some(7.0));
+        assert_eq!(comparison.symptoms.len(), 1);
+        let fatigue = &comparison.symptoms[0];
+        assert_eq!((fatigue.mean_severity_tagged, fatigue.frequency_tagged), (Some(3.0), Some(0.5)));
+        assert_eq!((fatigue.mean_severity_untagged, fatigue.frequency_untagged), (Some(0.0), Some(0.0)));
+
+        assert!(compare_tag(&conn, 2, &range).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+// Vital signs and body measurements. Normal ranges live in `settings` as
+// `vital_<kind>_low` / `vital_<kind>_high` so flags follow the user's own
+// configuration rather than being stored per reading.
+
+use crate::date_range::DateRange;
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub struct VitalKind {
+    pub kind: &'static str,
+    pub label: &'static str,
+    pub unit: &'static str,
+    pub default_low: Option<f64>,
+    pub default_high: Option<f64>,
+    /// Values outside this are rejected as entry mistakes.
+    pub plausible: (f64, f64),
+}
+
+pub const VITAL_KINDS: [VitalKind; 6] = [
+    VitalKind { kind: "systolic_bp", label: "Systolic BP", unit: "mmHg", default_low: Some(90.0), default_high: Some(120.0), plausible: (40.0, 300.0) },
+    VitalKind { kind: "diastolic_bp", label: "Diastolic BP", unit: "mmHg", default_low: Some(60.0), default_high: Some(80.0), plausible: (20.0, 200.0) },
+    VitalKind { kind: "heart_rate", label: "Heart Rate", unit: "bpm", default_low: Some(60.0), default_high: Some(100.0), plausible: (20.0, 300.0) },
+    VitalKind { kind: "temperature_c", label: "Temperature", unit: "°C", default_low: Some(36.1), default_high: Some(37.5), plausible: (25.0, 45.0) },
+    VitalKind { kind: "weight_kg", label: "Weight", unit: "kg", default_low: None, default_high: None, plausible: (1.0, 500.0) },
+    VitalKind { kind: "spo2", label: "SpO2", unit: "%", default_low: Some(95.0), default_high: Some(100.0), plausible: (50.0, 100.0) },
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct VitalReading {
+    pub id: Option<i64>,
+    pub measured_at: String,
+    pub systolic_bp: Option<f64>,
+    pub diastolic_bp: Option<f64>,
+    pub heart_rate: Option<f64>,
+    pub temperature_c: Option<f64>,
+    pub weight_kg: Option<f64>,
+    pub spo2: Option<f64>,
+    pub notes: String,
+    #[serde(default)]
+    pub flags: Vec<VitalFlag>,
+}
+
+impl VitalReading {
+    pub fn value(&self, kind: &str) -> Option<f64> {
+        match kind {
+            "systolic_bp" => self.systolic_bp,
+            "diastolic_bp" => self.diastolic_bp,
+            "heart_rate" => self.heart_rate,
+            "temperature_c" => self.temperature_c,
+            "weight_kg" => self.weight_kg,
+            "spo2" => self.spo2,
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct VitalFlag {
+    pub kind: String,
+    pub value: f64,
+    pub flag: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VitalRange {
+    pub kind: String,
+    pub label: String,
+    pub unit: String,
+    pub low: Option<f64>,
+    pub high: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VitalTrendPoint {
+    pub measured_at: String,
+    pub value: f64,
+    pub flag: String,
+    pub ref_range_low: Option<f64>,
+    pub ref_range_high: Option<f64>,
+}
+
+pub fn is_vital_kind(kind: &str) -> bool {
+    VITAL_KINDS.iter().any(|k| k.kind == kind)
+}
+
+/// `measured_at` must be "YYYY-MM-DD HH:MM", optionally with seconds or a "T"
+/// separator: the forms SQLite's date functions read.
+pub fn validate_measured_at(measured_at: &str) -> Result<(), String> {
+    let valid = ["%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .any(|format| NaiveDateTime::parse_from_str(measured_at, format).is_ok());
+    if !valid {
+        return Err(format!("Invalid time (expected YYYY-MM-DD HH:MM): {}", measured_at));
+    }
+    Ok(())
+}
+
+/// A reading needs a valid time and at least one measurement, each within
+/// its kind's plausible bounds.
+pub fn validate_reading(reading: &VitalReading) -> Result<(), String> {
+    validate_measured_at(&reading.measured_at)?;
+    let mut measured = false;
+    for k in &VITAL_KINDS {
+        let Some(value) = reading.value(k.kind) else { continue };
+        let (min, max) = k.plausible;
+        if !(value.is_finite() && (min..=max).contains(&value)) {
+            return Err(format!("{} must be between {} and {} {}", k.label, min, max, k.unit));
+        }
+        measured = true;
+    }
+    if !measured {
+        return Err("Enter at least one measurement".to_string());
+    }
+    Ok(())
+}
+
+/// A normal range needs low below high; either bound may be left open.
+pub fn validate_range(low: Option<f64>, high: Option<f64>) -> Result<(), String> {
+    if low.into_iter().chain(high).any(|v| !v.is_finite()) {
+        return Err("Range bounds must be numbers".to_string());
+    }
+    if let (Some(low), Some(high)) = (low, high) {
+        if low >= high {
+            return Err(format!("The low bound ({}) must be below the high bound ({})", low, high));
+        }
+    }
+    Ok(())
+}
+
+fn setting_f64(conn: &Connection, key: &str) -> Option<Option<f64>> {
+    conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |r| {
+        r.get::<_, String>(0)
+    })
+    .ok()
+    .map(|v| v.parse().ok())
+}
+
+/// Configured normal ranges, falling back to the defaults for any kind the
+/// user hasn't changed. An empty setting means "no bound".
+pub fn ranges(conn: &Connection) -> Vec<VitalRange> {
+    VITAL_KINDS
+        .iter()
+        .map(|k| VitalRange {
+            kind: k.kind.to_string(),
+            label: k.label.to_string(),
+            unit: k.unit.to_string(),
+            low: setting_f64(conn, &format!("vital_{}_low", k.kind)).unwrap_or(k.default_low),
+            high: setting_f64(conn, &format!("vital_{}_high", k.kind)).unwrap_or(k.default_high),
+        })
+        .collect()
+}
+
+pub fn flag_value(value: f64, low: Option<f64>, high: Option<f64>) -> &'static str {
+    match (low, high) {
+        (Some(l), _) if value < l => "L",
+        (_, Some(h)) if value > h => "H",
+        _ => "N",
+    }
+}
+
+fn apply_flags(reading: &mut VitalReading, ranges: &[VitalRange]) {
+    reading.flags = ranges
+        .iter()
+        .filter_map(|r| {
+            let value = reading.value(&r.kind)?;
+            let flag = flag_value(value, r.low, r.high);
+            (flag != "N").then(|| VitalFlag {
+                kind: r.kind.clone(),
+                value,
+                flag: flag.to_string(),
+            })
+        })
+        .collect();
+}
+
+fn row_to_reading(row: &rusqlite::Row) -> rusqlite::Result<VitalReading> {
+    Ok(VitalReading {
+        id: Some(row.get(0)?),
+        measured_at: row.get(1)?,
+        systolic_bp: row.get(2)?,
+        diastolic_bp: row.get(3)?,
+        heart_rate: row.get(4)?,
+        temperature_c: row.get(5)?,
+        weight_kg: row.get(6)?,
+        spo2: row.get(7)?,
+        notes: row.get(8)?,
+        flags: Vec::new(),
+    })
+}
+
+/// Readings whose date falls within `start_date..=end_date`, flagged against
+/// the configured ranges.
+pub fn query_readings(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<VitalReading>, String> {
+    let ranges = ranges(conn);
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, measured_at, systolic_bp, diastolic_bp, heart_rate, temperature_c, weight_kg, spo2, notes
+             FROM vital_readings
+             WHERE date(measured_at) >= ?1 AND date(measured_at) <= ?2
+             ORDER BY measured_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let mut readings = stmt
+        .query_map(params![start_date, end_date], row_to_reading)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    for r in &mut readings {
+        apply_flags(r, &ranges);
+    }
+    Ok(readings)
+}
+
+pub fn query_trends(conn: &Connection, kind: &str, range: &DateRange) -> Result<Vec<VitalTrendPoint>, String> {
+    if !is_vital_kind(kind) {
+        return Err(format!("Unknown vital sign: {}", kind));
+    }
+    let normal = ranges(conn).into_iter().find(|r| r.kind == kind);
+    let (low, high) = normal.map(|r| (r.low, r.high)).unwrap_or((None, None));
+    // `kind` is checked against VITAL_KINDS above, so it is safe to use as a column name.
+    let sql = format!(
+        "SELECT measured_at, {kind} FROM vital_readings
+         WHERE {kind} IS NOT NULL
+           AND date(measured_at) >= ?1 AND date(measured_at) <= ?2
+         ORDER BY measured_at ASC"
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![range.start_date, range.end_date], |row| {
+            let value: f64 = row.get(1)?;
+            Ok(VitalTrendPoint {
+                measured_at: row.get(0)?,
+                value,
+                flag: flag_value(value, low, high).to_string(),
+                ref_range_low: low,
+                ref_range_high: high,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use std::path::Path;
+
+    fn reading(measured_at: &str) -> VitalReading {
+        VitalReading {
+            id: None,
+            measured_at: measured_at.to_string(),
+            systolic_bp: None,
+            diastolic_bp: None,
+            heart_rate: None,
+            temperature_c: None,
+            weight_kg: None,
+            spo2: None,
+            notes: String::new(),
+            flags: Vec::new(),
+        }
+    }
+
+    fn insert(conn: &Connection, r: &VitalReading) {
+        conn.execute(
+            "INSERT INTO vital_readings (measured_at, systolic_bp, heart_rate, spo2) VALUES (?1, ?2, ?3, ?4)",
+            params![r.measured_at, r.systolic_bp, r.heart_rate, r.spo2],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn readings_need_a_time_and_plausible_measurements() {
+        let valid = VitalReading { heart_rate: Some(72.0), ..reading("2024-03-01 08:30") };
+        validate_reading(&valid).unwrap();
+        validate_reading(&VitalReading { measured_at: "2024-03-01T08:30:15".to_string(), ..valid.clone() }).unwrap();
+
+        assert!(validate_reading(&reading("2024-03-01 08:30")).is_err());
+        assert!(validate_reading(&VitalReading { measured_at: "2024-03-01".to_string(), ..valid.clone() }).is_err());
+        assert!(validate_reading(&VitalReading { heart_rate: Some(-5.0), ..valid.clone() }).is_err());
+        assert!(validate_reading(&VitalReading { spo2: Some(150.0), ..valid.clone() }).is_err());
+        assert!(validate_reading(&VitalReading { weight_kg: Some(0.0), ..valid.clone() }).is_err());
+        assert!(validate_reading(&VitalReading { temperature_c: Some(f64::NAN), ..valid }).is_err());
+    }
+
+    #[test]
+    fn ranges_need_low_below_high() {
+        validate_range(Some(60.0), Some(100.0)).unwrap();
+        validate_range(None, Some(100.0)).unwrap();
+        assert!(validate_range(Some(100.0), Some(100.0)).is_err());
+        assert!(validate_range(Some(f64::INFINITY), None).is_err());
+    }
+
+    #[test]
+    fn readings_are_flagged_against_configured_ranges() {
+        let db = Database::open(Path::new(":memory:")).unwrap();
+        let conn = db.conn.lock().unwrap();
+        conn.execute("INSERT INTO settings (key, value) VALUES ('vital_heart_rate_high', '90'), ('vital_spo2_low', '')", [])
+            .unwrap();
+        insert(&conn, &VitalReading { heart_rate: Some(95.0), spo2: Some(91.0), ..reading("2024-03-01 08:30") });
+        insert(&conn, &VitalReading { systolic_bp: Some(85.0), ..reading("2024-03-02 21:00") });
+        insert(&conn, &VitalReading { heart_rate: Some(70.0), ..reading("2024-03-03 07:00") });
+
+        let readings = query_readings(&conn, "2024-03-01", "2024-03-02").unwrap();
+        let flags: Vec<Vec<(String, String)>> = readings
+            .iter()
+            .map(|r| r.flags.iter().map(|f| (f.kind.clone(), f.flag.clone())).collect())
+            .collect();
+        // The emptied SpO2 bound leaves 91% unflagged
+        assert_eq!(flags, [vec![("heart_rate".to_string(), "H".to_string())], vec![("systolic_bp".to_string(), "L".to_string())]]);
+
+        let range = DateRange { start_date: "2024-03-01".to_string(), end_date: "2024-03-31".to_string() };
+        let trend = query_trends(&conn, "heart_rate", &range).unwrap();
+        let points: Vec<(f64, &str)> = trend.iter().map(|p| (p.value, p.flag.as_str())).collect();
+        assert_eq!(points, [(95.0, "H"), (70.0, "N")]);
+        assert!(query_trends(&conn, "notes", &range).is_err());
+    }
+}
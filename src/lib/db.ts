@@ -3,15 +3,48 @@ import type {
   LabSession,
   LabResult,
   LabSessionWithResults,
-  TrendPoint,
+  DateRangeQuery,
+  LabTrend,
+  TrendOverlay,
+  MedicationEvent,
+  MedicationEffect,
+  TherapeuticRange,
+  WarfarinDose,
+  InrReport,
+  VitalKind,
+  VitalReading,
+  VitalReadings,
+  VitalRange,
+  VitalTrend,
+  Flare,
+  FlareWithLabs,
+  FlareSuggestions,
+  CorrelationResult,
+  CrossCorrelationResult,
+  TestSummary,
+  CalendarHeatmap,
+  RollupPeriod,
+  Rollup,
+  NoteHit,
+  McpWrite,
+  McpPolicy,
+  McpAccess,
+  McpPolicyOptions,
+  Tag,
+  SymptomLogTags,
+  TagComparison,
   AbnormalResult,
   CustomLabTest,
-  WellnessTrendPoint,
-  SymptomTrendPoint,
+  WellnessTrend,
+  SymptomTrend,
+  AllSymptomTrends,
   SymptomNameEntry,
   Symptom,
   SymptomEntry,
   DayLog,
+  IntradayTrend,
+  BodyLocation,
+  BodyLocationSummary,
 } from './types';
 
 // Lab commands
@@ -52,21 +85,228 @@ export async function deleteCustomLabTest(id: number): Promise<void> {
   return invoke('delete_custom_lab_test', { id });
 }
 
-export async function getTrends(testName: string, days: number): Promise<TrendPoint[]> {
-  return invoke('get_trends', { testName, days });
+export async function getTrends(testName: string, range: DateRangeQuery, tagId?: number): Promise<LabTrend> {
+  return invoke('get_trends', { testName, ...range, tagId });
+}
+
+export async function getTrendsWithMedications(testName: string, range: DateRangeQuery): Promise<TrendOverlay> {
+  return invoke('get_trends_with_medications', { testName, ...range });
 }
 
 export async function getAllTestNames(): Promise<string[]> {
   return invoke('get_all_test_names');
 }
 
+// Medication commands
+export async function getMedicationEvents(): Promise<MedicationEvent[]> {
+  return invoke('get_medication_events');
+}
+
+export async function saveMedicationEvent(event: MedicationEvent): Promise<number> {
+  return invoke('save_medication_event', { event });
+}
+
+export async function deleteMedicationEvent(id: number): Promise<void> {
+  return invoke('delete_medication_event', { id });
+}
+
+export async function getMedicationEffect(
+  eventId: number,
+  testName: string,
+  windowDays: number,
+): Promise<MedicationEffect> {
+  return invoke('get_medication_effect', { eventId, testName, windowDays });
+}
+
+// Anticoagulation (INR) commands
+export async function getInrTherapeuticRange(): Promise<TherapeuticRange> {
+  return invoke('get_inr_therapeutic_range');
+}
+
+export async function saveInrTherapeuticRange(low: number, high: number): Promise<void> {
+  return invoke('save_inr_therapeutic_range', { low, high });
+}
+
+export async function getWarfarinDoses(): Promise<WarfarinDose[]> {
+  return invoke('get_warfarin_doses');
+}
+
+export async function saveWarfarinDose(dose: WarfarinDose): Promise<number> {
+  return invoke('save_warfarin_dose', { dose });
+}
+
+export async function deleteWarfarinDose(id: number): Promise<void> {
+  return invoke('delete_warfarin_dose', { id });
+}
+
+export async function getInrReport(range: DateRangeQuery): Promise<InrReport> {
+  return invoke('get_inr_report', { ...range });
+}
+
+// Vital sign commands
+export async function getVitals(range: DateRangeQuery): Promise<VitalReadings> {
+  return invoke('get_vitals', { ...range });
+}
+
+export async function saveVitalReading(reading: VitalReading): Promise<number> {
+  return invoke('save_vital_reading', { reading });
+}
+
+export async function deleteVitalReading(id: number): Promise<void> {
+  return invoke('delete_vital_reading', { id });
+}
+
+export async function getVitalRanges(): Promise<VitalRange[]> {
+  return invoke('get_vital_ranges');
+}
+
+export async function saveVitalRange(kind: VitalKind, low: number | null, high: number | null): Promise<void> {
+  return invoke('save_vital_range', { kind, low, high });
+}
+
+export async function getVitalTrends(kind: VitalKind, range: DateRangeQuery): Promise<VitalTrend> {
+  return invoke('get_vital_trends', { kind, ...range });
+}
+
+// Flare commands
+export async function getFlares(): Promise<FlareWithLabs[]> {
+  return invoke('get_flares');
+}
+
+export async function saveFlare(flare: Flare): Promise<number> {
+  return invoke('save_flare', { flare });
+}
+
+export async function deleteFlare(id: number): Promise<void> {
+  return invoke('delete_flare', { id });
+}
+
+export async function suggestFlares(
+  range: DateRangeQuery,
+  minDays: number | null = null,
+  maxGapDays: number | null = null,
+): Promise<FlareSuggestions> {
+  return invoke('suggest_flares', { ...range, minDays, maxGapDays });
+}
+
+// Analysis commands
+export async function getLabSymptomCorrelation(
+  testName: string,
+  symptomId: number | null,
+  windowDays: number,
+  range: DateRangeQuery,
+): Promise<CorrelationResult> {
+  return invoke('get_lab_symptom_correlation', { testName, symptomId, windowDays, ...range });
+}
+
+export async function getSymptomCrossCorrelation(
+  symptomAId: number | null,
+  symptomBId: number | null,
+  maxLag: number,
+  range: DateRangeQuery,
+): Promise<CrossCorrelationResult> {
+  return invoke('get_symptom_cross_correlation', { symptomAId, symptomBId, maxLag, ...range });
+}
+
+export async function getTestSummary(testName: string, range: DateRangeQuery): Promise<TestSummary> {
+  return invoke('get_test_summary', { testName, ...range });
+}
+
+export async function getCalendarHeatmap(range: DateRangeQuery): Promise<CalendarHeatmap> {
+  return invoke('get_calendar_heatmap', { ...range });
+}
+
+export async function getRollups(period: RollupPeriod, range: DateRangeQuery): Promise<Rollup> {
+  return invoke('get_rollups', { period, ...range });
+}
+
+export async function searchNotes(query: string, limit?: number): Promise<NoteHit[]> {
+  return invoke('search_notes', { query, limit });
+}
+
+// MCP write review
+export async function getMcpWrites(limit?: number): Promise<McpWrite[]> {
+  return invoke('get_mcp_writes', { limit });
+}
+
+export async function undoMcpWrite(id: number): Promise<void> {
+  return invoke('undo_mcp_write', { id });
+}
+
+// MCP access log
+export async function getMcpAccessLog(limit?: number): Promise<McpAccess[]> {
+  return invoke('get_mcp_access_log', { limit });
+}
+
+export async function clearMcpAccessLog(): Promise<void> {
+  return invoke('clear_mcp_access_log');
+}
+
+// MCP access policy
+export async function getMcpPolicy(): Promise<McpPolicy> {
+  return invoke('get_mcp_policy');
+}
+
+export async function saveMcpPolicy(policy: McpPolicy): Promise<void> {
+  return invoke('save_mcp_policy', { policy });
+}
+
+export async function getMcpPolicyOptions(): Promise<McpPolicyOptions> {
+  return invoke('get_mcp_policy_options');
+}
+
+// Tag commands
+export async function getTags(): Promise<Tag[]> {
+  return invoke('get_tags');
+}
+
+export async function saveTag(tag: Tag): Promise<number> {
+  return invoke('save_tag', { tag });
+}
+
+export async function deleteTag(id: number): Promise<void> {
+  return invoke('delete_tag', { id });
+}
+
+export async function getDayTags(date: string): Promise<Tag[]> {
+  return invoke('get_day_tags', { date });
+}
+
+export async function setDayTags(date: string, tagIds: number[]): Promise<void> {
+  return invoke('set_day_tags', { date, tagIds });
+}
+
+export async function getLabSessionTags(sessionId: number): Promise<Tag[]> {
+  return invoke('get_lab_session_tags', { sessionId });
+}
+
+export async function setLabSessionTags(sessionId: number, tagIds: number[]): Promise<void> {
+  return invoke('set_lab_session_tags', { sessionId, tagIds });
+}
+
+export async function getSymptomLogTags(date: string): Promise<SymptomLogTags[]> {
+  return invoke('get_symptom_log_tags', { date });
+}
+
+export async function setSymptomLogTags(date: string, symptomId: number, tagIds: number[]): Promise<void> {
+  return invoke('set_symptom_log_tags', { date, symptomId, tagIds });
+}
+
+export async function getTagComparison(tagId: number, range: DateRangeQuery): Promise<TagComparison> {
+  return invoke('get_tag_comparison', { tagId, ...range });
+}
+
 // Symptom trend commands
-export async function getWellnessTrends(days: number): Promise<WellnessTrendPoint[]> {
-  return invoke('get_wellness_trends', { days });
+export async function getWellnessTrends(range: DateRangeQuery, tagId?: number): Promise<WellnessTrend> {
+  return invoke('get_wellness_trends', { ...range, tagId });
+}
+
+export async function getSymptomTrends(symptomId: number, range: DateRangeQuery, tagId?: number): Promise<SymptomTrend> {
+  return invoke('get_symptom_trends', { symptomId, ...range, tagId });
 }
 
-export async function getSymptomTrends(symptomId: number, days: number): Promise<SymptomTrendPoint[]> {
-  return invoke('get_symptom_trends', { symptomId, days });
+export async function getAllSymptomTrends(range: DateRangeQuery, tagId?: number): Promise<AllSymptomTrends> {
+  return invoke('get_all_symptom_trends', { ...range, tagId });
 }
 
 export async function getActiveSymptomNames(): Promise<SymptomNameEntry[]> {
@@ -103,6 +343,21 @@ export async function saveSymptomLog(
   return invoke('save_symptom_log', { date, entries, wellness, notes });
 }
 
+export async function getSymptomIntraday(symptomId: number, range: DateRangeQuery): Promise<IntradayTrend> {
+  return invoke('get_symptom_intraday', { symptomId, ...range });
+}
+
+export async function getBodyLocations(): Promise<BodyLocation[]> {
+  return invoke('get_body_locations');
+}
+
+export async function getBodyLocationSummary(
+  range: DateRangeQuery,
+  symptomId: number | null = null,
+): Promise<BodyLocationSummary> {
+  return invoke('get_body_location_summary', { ...range, symptomId });
+}
+
 // Settings
 export async function getSetting(key: string): Promise<string> {
   return invoke('get_setting', { key });
@@ -112,13 +367,31 @@ export async function setSetting(key: string, value: string): Promise<void> {
   return invoke('set_setting', { key, value });
 }
 
+export async function regenerateMcpHttpToken(): Promise<string> {
+  return invoke('regenerate_mcp_http_token');
+}
+
 // Export
 export async function exportData(
   startDate: string,
   endDate: string,
   includeLabs: boolean,
   includeSymptoms: boolean,
+  includeVitals: boolean,
+  includeFlares: boolean,
+  summaryPeriod: RollupPeriod | null,
+  tagId: number | null,
   format: string,
 ): Promise<string> {
-  return invoke('export_data', { startDate, endDate, includeLabs, includeSymptoms, format });
+  return invoke('export_data', {
+    startDate,
+    endDate,
+    includeLabs,
+    includeSymptoms,
+    includeVitals,
+    includeFlares,
+    summaryPeriod,
+    tagId,
+    format,
+  });
 }
@@ -32,6 +32,389 @@ export interface TrendPoint {
   ref_range_high: number | null;
 }
 
+export type MedicationEventType = 'start' | 'stop' | 'dose_change';
+
+export interface MedicationEvent {
+  id: number | null;
+  medication: string;
+  event_date: string;
+  event_type: MedicationEventType;
+  dose: string;
+  notes: string;
+}
+
+/** Either `days` (counting back from `endDate` or today) or an explicit `startDate`. */
+export interface DateRangeQuery {
+  days?: number;
+  startDate?: string;
+  endDate?: string;
+}
+
+export interface LabTrend {
+  start_date: string;
+  end_date: string;
+  points: TrendPoint[];
+}
+
+export interface TrendOverlay {
+  start_date: string;
+  end_date: string;
+  points: TrendPoint[];
+  medication_events: MedicationEvent[];
+}
+
+export interface WindowSummary {
+  start_date: string;
+  end_date: string;
+  count: number;
+  mean: number | null;
+  median: number | null;
+  abnormal_count: number;
+}
+
+export interface MedicationEffect {
+  event: MedicationEvent;
+  test_name: string;
+  before: WindowSummary;
+  after: WindowSummary;
+}
+
+export interface TherapeuticRange {
+  low: number;
+  high: number;
+}
+
+export interface WarfarinDose {
+  id: number | null;
+  week_start: string;
+  weekly_dose_mg: number;
+  notes: string;
+}
+
+export interface InrPoint {
+  test_date: string;
+  value: number;
+  in_range: boolean;
+}
+
+export interface TimeInRange {
+  days_assessed: number;
+  days_in_range: number;
+  days_below: number;
+  days_above: number;
+  ttr_percent: number | null;
+}
+
+export interface OutOfRangeStreak {
+  direction: 'low' | 'high';
+  start_date: string;
+  end_date: string;
+  result_count: number;
+  extreme_value: number;
+}
+
+export interface InrReport {
+  start_date: string;
+  end_date: string;
+  therapeutic_range: TherapeuticRange;
+  points: InrPoint[];
+  time_in_range: TimeInRange;
+  streaks: OutOfRangeStreak[];
+  doses: WarfarinDose[];
+}
+
+export type VitalKind = 'systolic_bp' | 'diastolic_bp' | 'heart_rate' | 'temperature_c' | 'weight_kg' | 'spo2';
+
+export interface VitalFlag {
+  kind: VitalKind;
+  value: number;
+  flag: string;
+}
+
+export interface VitalReading {
+  id: number | null;
+  measured_at: string;
+  systolic_bp: number | null;
+  diastolic_bp: number | null;
+  heart_rate: number | null;
+  temperature_c: number | null;
+  weight_kg: number | null;
+  spo2: number | null;
+  notes: string;
+  flags: VitalFlag[];
+}
+
+export interface VitalReadings {
+  start_date: string;
+  end_date: string;
+  readings: VitalReading[];
+}
+
+export interface VitalRange {
+  kind: VitalKind;
+  label: string;
+  unit: string;
+  low: number | null;
+  high: number | null;
+}
+
+export interface VitalTrendPoint {
+  measured_at: string;
+  value: number;
+  flag: string;
+  ref_range_low: number | null;
+  ref_range_high: number | null;
+}
+
+export interface VitalTrend {
+  start_date: string;
+  end_date: string;
+  points: VitalTrendPoint[];
+}
+
+export interface Flare {
+  id: number | null;
+  start_date: string;
+  end_date: string | null;
+  severity: number;
+  affected_systems: string[];
+  triggers: string;
+  treatment_notes: string;
+}
+
+export interface FlareLabResult {
+  test_name: string;
+  panel: string;
+  value: number | null;
+  text_value: string;
+  unit: string;
+  flag: string;
+}
+
+export interface FlareLabSession {
+  session_id: number;
+  test_date: string;
+  lab_name: string;
+  results: FlareLabResult[];
+}
+
+export interface FlareWithLabs {
+  flare: Flare;
+  labs: FlareLabSession[];
+}
+
+export interface FlareCandidate {
+  start_date: string;
+  end_date: string;
+  flagged_days: number;
+  peak_burden: number;
+  mean_wellness: number | null;
+  affected_systems: string[];
+  overlaps_existing: boolean;
+}
+
+export interface FlareSuggestions {
+  start_date: string;
+  end_date: string;
+  burden_threshold: number;
+  wellness_threshold: number | null;
+  candidates: FlareCandidate[];
+}
+
+export interface AlignedPair {
+  test_date: string;
+  lab_value: number;
+  symptom_mean: number;
+  days_logged: number;
+}
+
+export interface CorrelationResult {
+  test_name: string;
+  target: string;
+  start_date: string;
+  end_date: string;
+  window_days: number;
+  n: number;
+  pearson: number | null;
+  spearman: number | null;
+  pairs: AlignedPair[];
+}
+
+export interface LagCorrelation {
+  lag: number;
+  n: number;
+  pearson: number | null;
+}
+
+export interface CrossCorrelationResult {
+  series_a: string;
+  series_b: string;
+  start_date: string;
+  end_date: string;
+  days_logged_a: number;
+  days_logged_b: number;
+  lags: LagCorrelation[];
+  best_lag: number | null;
+}
+
+export interface ConsecutiveChange {
+  from_date: string;
+  to_date: string;
+  from_value: number;
+  to_value: number;
+  percent_change: number | null;
+  exceeds_rcv: boolean | null;
+}
+
+export interface TestSummary {
+  test_name: string;
+  unit: string;
+  start_date: string;
+  end_date: string;
+  count: number;
+  min: number | null;
+  max: number | null;
+  mean: number | null;
+  latest_value: number | null;
+  latest_date: string | null;
+  baseline_value: number | null;
+  baseline_date: string | null;
+  percent_change_from_baseline: number | null;
+  slope_per_month: number | null;
+  cv_biological: number | null;
+  cv_analytical: number | null;
+  rcv_percent: number | null;
+  changes: ConsecutiveChange[];
+}
+
+export interface CalendarDay {
+  date: string;
+  logged: boolean;
+  wellness_score: number | null;
+  max_severity: number | null;
+  symptoms_present: number;
+  labs_drawn: boolean;
+}
+
+export interface CalendarHeatmap {
+  start_date: string;
+  end_date: string;
+  days: CalendarDay[];
+}
+
+export type RollupPeriod = 'week' | 'month';
+
+export interface SymptomRollup {
+  symptom_id: number;
+  name: string;
+  days_present: number;
+  frequency: number;
+  mean_severity: number;
+  max_severity: number;
+}
+
+export interface RollupBucket {
+  label: string;
+  start_date: string;
+  end_date: string;
+  days_logged: number;
+  mean_wellness: number | null;
+  symptoms: SymptomRollup[];
+}
+
+export interface Rollup {
+  period: RollupPeriod;
+  start_date: string;
+  end_date: string;
+  buckets: RollupBucket[];
+}
+
+export type NoteSource = 'lab_session' | 'symptom_log' | 'daily_summary';
+
+export interface NoteHit {
+  source: NoteSource;
+  source_id: number;
+  date: string;
+  context: string;
+  snippet: string;
+}
+
+export interface McpWrite {
+  id: number;
+  tool: string;
+  summary: string;
+  record_date: string;
+  session_id: number | null;
+  created_at: string;
+  undone_at: string | null;
+}
+
+export interface McpAccess {
+  id: number;
+  accessed_at: string;
+  client: string;
+  kind: 'tool' | 'resource' | 'prompt';
+  name: string;
+  params: string;
+  row_count: number | null;
+  error: string | null;
+}
+
+export interface McpPolicy {
+  blocked_categories: string[];
+  blocked_tools: string[];
+  hidden_panels: string[];
+  hidden_symptom_categories: string[];
+  max_days: number | null;
+  redact_note_categories: string[];
+}
+
+export interface McpToolInfo {
+  name: string;
+  categories: string[];
+  label: string;
+  writes: boolean;
+}
+
+export interface McpPolicyOptions {
+  tools: McpToolInfo[];
+  categories: string[];
+  note_categories: string[];
+  panels: string[];
+  symptom_categories: string[];
+}
+
+export interface Tag {
+  id: number | null;
+  name: string;
+  color: string;
+}
+
+export interface SymptomLogTags {
+  symptom_id: number;
+  tags: Tag[];
+}
+
+export interface TagSymptomComparison {
+  symptom_id: number;
+  name: string;
+  mean_severity_tagged: number | null;
+  mean_severity_untagged: number | null;
+  frequency_tagged: number | null;
+  frequency_untagged: number | null;
+}
+
+export interface TagComparison {
+  tag: Tag;
+  start_date: string;
+  end_date: string;
+  tagged_days: number;
+  untagged_days: number;
+  mean_wellness_tagged: number | null;
+  mean_wellness_untagged: number | null;
+  symptoms: TagSymptomComparison[];
+}
+
 export interface Symptom {
   id: number | null;
   name: string;
@@ -41,10 +424,23 @@ export interface Symptom {
   sort_order: number;
 }
 
+export type TimeSlot = 'morning' | 'afternoon' | 'evening' | 'night';
+
+export interface IntradayEntry {
+  time_of_day: TimeSlot | string;
+  severity: number;
+  notes: string;
+}
+
+/** Leaving out `intraday`, `body_locations`, `duration_minutes` or `onset_time` keeps what is stored; `null` clears the last two. */
 export interface SymptomEntry {
   symptom_id: number;
   severity: number;
   notes: string;
+  intraday?: IntradayEntry[] | null;
+  body_locations?: string[];
+  duration_minutes?: number | null;
+  onset_time?: string | null;
 }
 
 export interface SymptomLogEntry {
@@ -53,6 +449,48 @@ export interface SymptomLogEntry {
   category: string;
   severity: number;
   notes: string;
+  intraday: IntradayEntry[];
+  body_locations: string[];
+  duration_minutes: number | null;
+  onset_time: string | null;
+}
+
+export interface BodyLocation {
+  key: string;
+  label: string;
+  is_joint: boolean;
+}
+
+export interface LocationDay {
+  date: string;
+  locations: string[];
+  location_count: number;
+  joint_count: number;
+}
+
+export interface LocationFrequency {
+  location: string;
+  days: number;
+}
+
+export interface BodyLocationSummary {
+  start_date: string;
+  end_date: string;
+  days: LocationDay[];
+  frequencies: LocationFrequency[];
+}
+
+export interface IntradayPoint {
+  date: string;
+  time_of_day: string;
+  severity: number;
+  notes: string;
+}
+
+export interface IntradayTrend {
+  start_date: string;
+  end_date: string;
+  points: IntradayPoint[];
 }
 
 export interface DayLog {
@@ -69,7 +507,37 @@ export interface WellnessTrendPoint {
 
 export interface SymptomTrendPoint {
   date: string;
+  severity: number;
   present: boolean;
+  has_notes: boolean;
+  rolling_7: number;
+  rolling_30: number;
+  days_since_last: number | null;
+}
+
+export interface WellnessTrend {
+  start_date: string;
+  end_date: string;
+  points: WellnessTrendPoint[];
+}
+
+export interface SymptomTrend {
+  start_date: string;
+  end_date: string;
+  points: SymptomTrendPoint[];
+}
+
+export interface SymptomTrendSeries {
+  symptom_id: number;
+  name: string;
+  category: string;
+  points: SymptomTrendPoint[];
+}
+
+export interface AllSymptomTrends {
+  start_date: string;
+  end_date: string;
+  symptoms: SymptomTrendSeries[];
 }
 
 export interface SymptomNameEntry {